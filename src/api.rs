@@ -0,0 +1,179 @@
+// Operations shared by the programmatic front-ends (`serve`, `rpc`).
+//
+// Reads go straight to `Database`. Writes are DIALOG command lines run
+// through `Dialog::run_line`, so they get the same validation, proposals,
+// undo log and audit trail as typed commands. A `learn` proposal waits for
+// `decide`, like it does in DIALOG.
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::bus::Bus;
+use crate::config::Config;
+use crate::db::Database;
+use crate::modules::dialog::Dialog;
+use crate::ui::notify::Notifier;
+
+// Longest path `path` searches unless asked otherwise.
+pub const DEFAULT_MAX_HOPS: usize = 6;
+
+#[derive(Deserialize)]
+pub struct NewConcept {
+    pub name: String,
+    pub definition: String,
+    pub confidence: Option<f64>,
+}
+
+#[derive(Deserialize)]
+pub struct NewRelation {
+    pub from: String,
+    #[serde(rename = "type")]
+    pub relation_type: String,
+    pub to: String,
+}
+
+#[derive(Deserialize)]
+pub struct NewEpisode {
+    pub outcome: String,
+    pub summary: String,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ErrorKind {
+    // Bad input, or DIALOG rejected the command.
+    Invalid,
+    NotFound,
+    // A proposal is (or is not) pending.
+    Conflict,
+    Internal,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ApiError {
+    #[serde(skip)]
+    pub kind: ErrorKind,
+    pub error: String,
+    // DIALOG's diagnostics, when a command line failed.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub transcript: Vec<String>,
+}
+
+impl ApiError {
+    pub fn new(kind: ErrorKind, error: impl Into<String>) -> Self {
+        Self { kind, error: error.into(), transcript: Vec::new() }
+    }
+}
+
+fn db_error(e: rusqlite::Error) -> ApiError {
+    ApiError::new(ErrorKind::Internal, format!("DB error: {}", e))
+}
+
+pub type ApiResult = Result<Value, ApiError>;
+
+pub struct Api {
+    db: Database,
+    dialog: Dialog,
+}
+
+impl Api {
+    /// Opens two connections to the configured database: one for reads, one
+    /// behind the DIALOG interpreter.
+    pub fn open(config: &Config) -> Result<Self, String> {
+        let open = || Database::init(&config.db_path).map_err(|e| format!("cannot open {}: {}", config.db_path, e));
+        Ok(Self {
+            db: open()?,
+            dialog: Dialog::new(open()?, Notifier::default(), Bus::default(), config),
+        })
+    }
+
+    /// The read connection, for queries this module doesn't wrap.
+    pub fn db(&self) -> &Database {
+        &self.db
+    }
+
+    pub fn concepts(&self, limit: usize) -> ApiResult {
+        self.db.list_concepts(limit).map(|v| json!(v)).map_err(db_error)
+    }
+
+    /// A concept with its relations.
+    pub fn concept(&self, name: &str) -> ApiResult {
+        let name = name.to_lowercase();
+        match self.db.get_concept(&name).map_err(db_error)? {
+            Some(c) => {
+                let rels = self.db.list_relations_for(&name, 200).map_err(db_error)?;
+                Ok(json!({ "concept": c, "relations": rels }))
+            }
+            None => Err(ApiError::new(ErrorKind::NotFound, format!("no concept '{}'", name))),
+        }
+    }
+
+    pub fn relations(&self, concept: &str, limit: usize) -> ApiResult {
+        self.db.list_relations_for(&concept.to_lowercase(), limit).map(|v| json!(v)).map_err(db_error)
+    }
+
+    pub fn episodes(&self, limit: usize) -> ApiResult {
+        self.db.list_episodes(limit).map(|v| json!(v)).map_err(db_error)
+    }
+
+    pub fn search(&self, query: &str, limit: usize) -> ApiResult {
+        self.db.search_concepts(query, limit).map(|v| json!(v)).map_err(db_error)
+    }
+
+    pub fn path(&self, from: &str, to: &str, max_hops: usize) -> ApiResult {
+        match self.db.find_path(&from.to_lowercase(), &to.to_lowercase(), max_hops).map_err(db_error)? {
+            Some(path) => Ok(json!({ "path": path })),
+            None => Err(ApiError::new(ErrorKind::NotFound, format!("no path from '{}' to '{}'", from, to))),
+        }
+    }
+
+    pub fn audit(&self, concept: Option<&str>, operator: Option<&str>, limit: usize) -> ApiResult {
+        self.db.list_audit(concept, operator, limit).map(|v| json!(v)).map_err(db_error)
+    }
+
+    /// Creates a proposal; it is stored only after `decide(true)`.
+    pub fn learn(&mut self, c: &NewConcept) -> ApiResult {
+        if self.dialog.pending().is_some() {
+            return Err(ApiError::new(
+                ErrorKind::Conflict,
+                "a proposal is already pending; confirm or reject it first",
+            ));
+        }
+        let flag = c.confidence.map(|x| format!("--confidence={} ", x)).unwrap_or_default();
+        // The definition is free text, taken as typed.
+        let mut out = self.run(&format!("learn {}{} is {}", flag, quote(&c.name), c.definition.trim()))?;
+        out["pending"] = json!(self.dialog.pending());
+        Ok(out)
+    }
+
+    pub fn pending(&self) -> Value {
+        json!({ "pending": self.dialog.pending() })
+    }
+
+    /// Confirms or rejects the pending proposal.
+    pub fn decide(&mut self, confirm: bool) -> ApiResult {
+        if self.dialog.pending().is_none() {
+            return Err(ApiError::new(ErrorKind::Conflict, "no proposal is pending"));
+        }
+        Ok(json!({ "transcript": self.dialog.decide(confirm) }))
+    }
+
+    pub fn relate(&mut self, r: &NewRelation) -> ApiResult {
+        self.run(&format!("rel {} {} {}", quote(&r.from), quote(&r.relation_type), quote(&r.to)))
+    }
+
+    pub fn record_episode(&mut self, e: &NewEpisode) -> ApiResult {
+        self.run(&format!("ep {} {}", quote(&e.outcome), e.summary.trim()))
+    }
+
+    /// Any DIALOG command line, e.g. "undo" or "show jwt".
+    pub fn run(&mut self, line: &str) -> ApiResult {
+        match self.dialog.run_line(line) {
+            Ok(transcript) => Ok(json!({ "transcript": transcript })),
+            Err(transcript) => Err(ApiError { kind: ErrorKind::Invalid, error: "rejected by DIALOG".into(), transcript }),
+        }
+    }
+}
+
+/// One DIALOG argument, whatever it contains.
+pub fn quote(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
@@ -1,67 +1,482 @@
 use std::{error::Error, io};
-use crossterm::event::{self, Event, KeyCode, KeyModifiers};
-use ratatui::{Terminal, backend::CrosstermBackend};
+use crossterm::event::{self, Event, KeyCode, KeyModifiers, MouseButton, MouseEventKind};
+use ratatui::{
+    Terminal,
+    backend::CrosstermBackend,
+    layout::{Constraint, Direction, Layout, Rect},
+};
 
-use crate::modules::{Module, console::Console, dialog::Dialog, graph::Graph};
-use crate::db::Database;
+use mother_terminal::bus::{Bus, Message};
+use mother_terminal::modules::{self, Action, Context, Module, ModuleSpec, Screen};
+use mother_terminal::config::{Config, CONFIG_PATH};
+use mother_terminal::db::{self, Database};
+use mother_terminal::keymap::{GlobalAction, Keymap};
+use mother_terminal::ui::{self, notify::{self, Notifier}};
 
-pub enum Screen {
-    Console,
-    Dialog,
-    Graph,
+use crate::term::{self, Signals};
+
+const STATUS_HINT: &str = "READY  [?] keys  [F9] notifications  [F5] split";
+
+// Panes of a new split when the current screen is one of them.
+const DEFAULT_SPLIT: (&str, &str) = ("dialog", "graph");
+
+const MIN_SPLIT: u16 = 20;
+const MAX_SPLIT: u16 = 80;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    Left,
+    Right,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Overlay {
+    Help,
+    Notifications,
+}
+
+/// Two screens side by side; `ratio` is the left pane's width in percent.
+#[derive(Clone, Copy)]
+pub struct Split {
+    pub left: Screen,
+    pub right: Screen,
+    pub ratio: u16,
+    pub focus: Side,
+}
+
+impl Split {
+    /// `pair` side by side if it contains `screen`; otherwise `screen` next to `pair.1`.
+    fn around(screen: Screen, pair: (Screen, Screen)) -> Self {
+        let (left, right) = if screen == pair.0 || screen == pair.1 { pair } else { (screen, pair.1) };
+        let focus = if screen == right { Side::Right } else { Side::Left };
+        Self { left, right, ratio: 50, focus }
+    }
+
+    fn focused(&self) -> Screen {
+        match self.focus {
+            Side::Left => self.left,
+            Side::Right => self.right,
+        }
+    }
+
+    /// Focuses the pane showing `screen`, or puts it in the focused pane.
+    fn show(&mut self, screen: Screen) {
+        if self.left == screen {
+            self.focus = Side::Left;
+        } else if self.right == screen {
+            self.focus = Side::Right;
+        } else {
+            match self.focus {
+                Side::Left => self.left = screen,
+                Side::Right => self.right = screen,
+            }
+        }
+    }
 }
 
 pub struct App {
+    pub specs: Vec<ModuleSpec>,
+    // One per spec, same order.
+    pub modules: Vec<Box<dyn Module>>,
+    // Screen receiving input: the only one shown, or the focused pane.
     pub screen: Screen,
-    pub console: Console,
-    pub dialog: Dialog,
-    pub graph: Graph,
+    pub split: Option<Split>,
+    pub config: Config,
+    // App's own connection, for undo/redo.
+    pub db: Database,
+    pub keymap: Keymap,
+    pub notifier: Notifier,
+    pub bus: Bus,
+    pub overlay: Option<Overlay>,
+    // Clickable tab and pane areas from the last frame.
+    pub tab_hits: Vec<(Rect, Screen)>,
+    pub pane_hits: Vec<(Rect, Side)>,
+}
+
+impl App {
+    /// Builds every registered module and its key bindings.
+    pub fn new(specs: Vec<ModuleSpec>, config: Config, notifier: Notifier) -> Result<Self, Box<dyn Error>> {
+        let bus = Bus::default();
+        let mut modules = Vec::new();
+        for spec in &specs {
+            let cx = Context { config: &config, notifier: &notifier, bus: bus.tagged(spec.name) };
+            modules.push((spec.build)(&cx)?);
+        }
+
+        let mut keymap = Keymap::new();
+        for (i, spec) in specs.iter().enumerate() {
+            keymap.add_screen(i, spec.title, &spec.keys);
+        }
+        for (i, (spec, module)) in specs.iter().zip(&modules).enumerate() {
+            keymap.register(i, spec.title, module.bindings());
+        }
+
+        Ok(Self {
+            specs,
+            modules,
+            screen: 0,
+            split: None,
+            db: Database::init(&config.db_path)?,
+            config,
+            keymap,
+            notifier,
+            bus,
+            overlay: None,
+            tab_hits: Vec::new(),
+            pane_hits: Vec::new(),
+        })
+    }
+
+    fn module(&mut self, screen: Screen) -> &mut dyn Module {
+        self.modules[screen].as_mut()
+    }
+
+    fn current(&mut self) -> &mut dyn Module {
+        self.module(self.screen)
+    }
+
+    fn find(&self, name: &str) -> Option<Screen> {
+        self.specs.iter().position(|s| s.name == name)
+    }
+
+    fn visible(&self) -> Vec<Screen> {
+        match self.split {
+            Some(s) if s.left != s.right => vec![s.left, s.right],
+            _ => vec![self.screen],
+        }
+    }
+
+    /// Runs `change`, then tells modules that appeared or disappeared.
+    fn transition(&mut self, change: impl FnOnce(&mut Self)) {
+        let before = self.visible();
+        change(self);
+        let after = self.visible();
+        for s in before.iter().filter(|s| !after.contains(s)) {
+            self.module(*s).on_leave();
+        }
+        for s in after.iter().filter(|s| !before.contains(s)) {
+            self.module(*s).on_enter();
+        }
+    }
+
+    fn show(&mut self, screen: Screen) {
+        self.transition(|app| {
+            if let Some(split) = app.split.as_mut() {
+                split.show(screen);
+                app.screen = split.focused();
+            } else {
+                app.screen = screen;
+            }
+        });
+    }
+
+    fn focus_pane(&mut self, side: Side) {
+        if let Some(split) = self.split.as_mut() {
+            split.focus = side;
+            self.screen = split.focused();
+        }
+    }
+
+    /// Carries out requests from module `from`; returns true if one asked to quit.
+    fn dispatch(&mut self, from: Screen, actions: Vec<Action>) -> bool {
+        for action in actions {
+            match action {
+                Action::Quit => return true,
+                Action::Navigate(name) => match self.find(&name) {
+                    Some(screen) => self.show(screen),
+                    None => self.notifier.warn(format!("No screen named '{}'.", name)),
+                },
+                // Through the bus so other modules see it too; `deliver` raises it.
+                Action::Notify(level, text) => self.bus.tagged(self.specs[from].name).publish(Message::Notify(level, text)),
+                Action::OpenConcept(name) => {
+                    match (0..self.modules.len()).find(|&i| self.modules[i].focus_concept(&name)) {
+                        Some(screen) => self.show(screen),
+                        None => self.notifier.warn("No screen can display concepts."),
+                    }
+                }
+                Action::Reopen(path) => self.reopen(from, path),
+                Action::ReloadConfig(config) => self.reload_config(from, config),
+            }
+        }
+        false
+    }
+
+    fn step_history(&mut self, redo: bool) {
+        self.db.set_command(if redo { "key Ctrl+Y" } else { "key Ctrl+U" });
+        let (verb, step) = if redo { ("redo", self.db.redo()) } else { ("undo", self.db.undo()) };
+        match step {
+            Ok(Some(label)) => {
+                let what = format!("{}: {}", verb, label);
+                self.notifier.info(what.clone());
+                self.bus.tagged("app").publish(Message::Reverted(what));
+            }
+            Ok(None) => self.notifier.warn(format!("Nothing to {}.", verb)),
+            Err(e) => self.notifier.error(format!("{} failed: {}", verb, e)),
+        }
+    }
+
+    /// Adopts a re-read mother.json and hands it to every module but `from`.
+    /// The database path only changes once a `Reopen` succeeds.
+    fn reload_config(&mut self, from: Screen, config: Config) {
+        let db_path = std::mem::take(&mut self.config.db_path);
+        self.config = Config { db_path, ..config };
+        for (i, module) in self.modules.iter_mut().enumerate() {
+            if i != from {
+                module.reload_config(&self.config);
+            }
+        }
+    }
+
+    /// Rebuilds every module but `from` against the database at `path`.
+    fn reopen(&mut self, from: Screen, path: String) {
+        match Database::init(&path) {
+            Ok(db) => self.db = db,
+            Err(e) => self.notifier.error(format!("Cannot reopen {} for undo: {}", path, e)),
+        }
+        self.config.db_path = path;
+        let visible = self.visible();
+        for i in (0..self.specs.len()).filter(|&i| i != from) {
+            let cx = Context {
+                config: &self.config,
+                notifier: &self.notifier,
+                bus: self.bus.tagged(self.specs[i].name),
+            };
+            match (self.specs[i].build)(&cx) {
+                Ok(module) => {
+                    self.modules[i] = module;
+                    if visible.contains(&i) {
+                        self.modules[i].on_enter();
+                    }
+                }
+                Err(e) => self.notifier.error(format!("{}: cannot reopen database: {}", self.specs[i].title, e)),
+            }
+        }
+    }
+
+    /// Hands queued bus messages to every module except their publisher.
+    fn deliver(&mut self) {
+        for env in self.bus.drain() {
+            if let Message::Notify(level, text) = &env.message {
+                self.notifier.notify(*level, text.clone());
+            }
+            for (spec, module) in self.specs.iter().zip(self.modules.iter_mut()) {
+                if spec.name != env.from {
+                    module.on_message(env.from, &env.message);
+                }
+            }
+        }
+    }
+
+    fn tick(&mut self) -> bool {
+        for screen in self.visible() {
+            let actions = self.module(screen).tick();
+            if self.dispatch(screen, actions) {
+                return true;
+            }
+        }
+        false
+    }
+
+    fn apply(&mut self, action: GlobalAction) {
+        match action {
+            GlobalAction::Quit | GlobalAction::Suspend | GlobalAction::Help | GlobalAction::Notifications => {}
+            GlobalAction::Undo => self.step_history(false),
+            GlobalAction::Redo => self.step_history(true),
+            GlobalAction::Show(screen) => self.show(screen),
+            GlobalAction::ToggleSplit => {
+                let pair = match (self.find(DEFAULT_SPLIT.0), self.find(DEFAULT_SPLIT.1)) {
+                    (Some(a), Some(b)) => (a, b),
+                    _ => (0, 1.min(self.specs.len() - 1)),
+                };
+                self.transition(|app| {
+                    app.split = match app.split {
+                        Some(_) => None,
+                        None => Some(Split::around(app.screen, pair)),
+                    };
+                });
+            }
+            GlobalAction::SwitchPane => {
+                if let Some(split) = self.split {
+                    self.focus_pane(match split.focus {
+                        Side::Left => Side::Right,
+                        Side::Right => Side::Left,
+                    });
+                }
+            }
+            GlobalAction::ResizeSplit(delta) => {
+                if let Some(split) = self.split.as_mut() {
+                    let ratio = split.ratio as i16 + delta;
+                    split.ratio = ratio.clamp(MIN_SPLIT as i16, MAX_SPLIT as i16) as u16;
+                }
+            }
+        }
+    }
+
+    fn toggle_overlay(&mut self, overlay: Overlay) {
+        self.overlay = if self.overlay == Some(overlay) { None } else { Some(overlay) };
+    }
+
+    fn render(&mut self, f: &mut ratatui::Frame) {
+        let rows = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(1), Constraint::Min(1), Constraint::Length(1)])
+            .split(f.area());
+
+        let visible: Vec<Screen> = match self.split {
+            Some(s) => vec![s.left, s.right],
+            None => vec![self.screen],
+        };
+        let labels: Vec<(Screen, String)> = self.specs
+            .iter()
+            .enumerate()
+            .map(|(i, s)| match s.keys.first() {
+                Some(k) => (i, format!("{} {}", k, s.title)),
+                None => (i, s.title.to_string()),
+            })
+            .collect();
+        let tabs: Vec<(Screen, &str)> = labels.iter().map(|(i, l)| (*i, l.as_str())).collect();
+        self.tab_hits = ui::render_tabs(f, rows[0], &tabs, self.screen, &visible);
+        self.pane_hits.clear();
+
+        match self.split {
+            None => self.current().render(f, rows[1]),
+            Some(split) => {
+                let panes = Layout::default()
+                    .direction(Direction::Horizontal)
+                    .constraints([
+                        Constraint::Percentage(split.ratio),
+                        Constraint::Percentage(100 - split.ratio),
+                    ])
+                    .split(rows[1]);
+                self.module(split.left).render(f, panes[0]);
+                self.module(split.right).render(f, panes[1]);
+                self.pane_hits = vec![(panes[0], Side::Left), (panes[1], Side::Right)];
+            }
+        }
+
+        notify::render_status_bar(f, rows[2], &self.notifier, STATUS_HINT);
+        match self.overlay {
+            Some(Overlay::Help) => ui::render_help(f, &self.keymap.help_lines(self.screen)),
+            Some(Overlay::Notifications) => notify::render_history(f, &self.notifier),
+            None => notify::render_toasts(f, rows[1], &self.notifier),
+        }
+    }
 }
 
+/// Returns normally on quit and on SIGTERM/SIGHUP/SIGINT, so `App` and its
+/// database connections are dropped before `main` restores the terminal.
 pub fn run() -> Result<(), Box<dyn Error>> {
     let backend = CrosstermBackend::new(io::stdout());
     let mut terminal = Terminal::new(backend)?;
+    let signals = Signals::register()?;
+
+    let config = Config::load(CONFIG_PATH)?;
+    db::set_operator(config.operator.as_deref());
 
-    // v0 simplicity: separate connections; later we’ll share one safely
-    let mut app = App {
-        screen: Screen::Console,
-        console: Console::new(),
-        dialog: Dialog::new(Database::init("mother.db")?),
-        graph: Graph::new(Database::init("mother.db")?),
-    };
+    // v0 simplicity: each module opens its own connection; later we’ll share one safely
+    let mut app = App::new(modules::registry(), config, Notifier::default())?;
+    app.current().on_enter();
 
     loop {
-        terminal.draw(|f| {
-            match app.screen {
-                Screen::Console => app.console.render(f),
-                Screen::Dialog => app.dialog.render(f),
-                Screen::Graph => app.graph.render(f),
+        if signals.quit_requested() {
+            return Ok(());
+        }
+        if signals.take_suspend() {
+            term::suspend()?;
+        }
+        if signals.take_resumed() {
+            // Back from the background: the shell may have reset the tty.
+            term::enter()?;
+            terminal.clear()?;
+        }
+
+        if app.tick() {
+            return Ok(());
+        }
+        app.deliver();
+        terminal.draw(|f| app.render(f))?;
+
+        if !event::poll(std::time::Duration::from_millis(100))? {
+            continue;
+        }
+
+        match event::read()? {
+            Event::Mouse(ev) if app.overlay.is_none() => {
+                let clicked_tab = app.tab_hits
+                    .iter()
+                    .find(|(r, _)| ui::contains(*r, ev.column, ev.row))
+                    .map(|(_, s)| *s);
+                if let (MouseEventKind::Down(MouseButton::Left), Some(screen)) = (ev.kind, clicked_tab) {
+                    app.show(screen);
+                    continue;
+                }
+                // In a split, the pane under the pointer gets the event; a click also focuses it.
+                let pane = app.pane_hits
+                    .iter()
+                    .find(|(r, _)| ui::contains(*r, ev.column, ev.row))
+                    .map(|(_, side)| *side);
+                match (app.split, pane) {
+                    (Some(split), Some(side)) => {
+                        if matches!(ev.kind, MouseEventKind::Down(_)) {
+                            app.focus_pane(side);
+                        }
+                        let screen = if side == Side::Left { split.left } else { split.right };
+                        let actions = app.module(screen).handle_mouse(ev);
+                        if app.dispatch(screen, actions) {
+                            return Ok(());
+                        }
+                    }
+                    _ => {
+                        let actions = app.current().handle_mouse(ev);
+                        if app.dispatch(app.screen, actions) {
+                            return Ok(());
+                        }
+                    }
+                }
             }
-        })?;
-
-        if event::poll(std::time::Duration::from_millis(100))? {
-            if let Event::Key(key) = event::read()? {
-                // Only treat Ctrl+<key> as global command shortcuts.
-                let is_ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
-
-                if is_ctrl {
-                    match key.code {
-                        KeyCode::Char('q') => return Ok(()),
-                        KeyCode::Char('c') => app.screen = Screen::Console,
-                        KeyCode::Char('d') => app.screen = Screen::Dialog,
-                        KeyCode::Char('g') => app.screen = Screen::Graph,
-                        _ => {}
+            Event::Key(key) => {
+                // While an overlay is up it swallows keys; Esc closes it.
+                if app.overlay.is_some() && key.code == KeyCode::Esc {
+                    app.overlay = None;
+                    continue;
+                }
+
+                let text_entry = app.current().captures_text();
+                match app.keymap.global_action(&key, text_entry) {
+                    Some(GlobalAction::Quit) => return Ok(()),
+                    Some(GlobalAction::Suspend) => {
+                        term::suspend()?;
+                        terminal.clear()?;
+                        continue;
+                    }
+                    Some(GlobalAction::Help) => {
+                        app.toggle_overlay(Overlay::Help);
+                        continue;
+                    }
+                    Some(GlobalAction::Notifications) => {
+                        app.toggle_overlay(Overlay::Notifications);
+                        continue;
                     }
+                    Some(action) => {
+                        app.apply(action);
+                        app.overlay = None;
+                        continue;
+                    }
+                    None => {}
+                }
+
+                // Unbound Ctrl+<key> combinations are reserved for global shortcuts.
+                if app.overlay.is_some() || key.modifiers.contains(KeyModifiers::CONTROL) {
                     continue;
                 }
 
                 // Otherwise: pass keystroke to current module (so typing works)
-                match app.screen {
-                    Screen::Console => app.console.handle_input(key),
-                    Screen::Dialog => app.dialog.handle_input(key),
-                    Screen::Graph => app.graph.handle_input(key),
+                let actions = app.current().handle_input(key);
+                if app.dispatch(app.screen, actions) {
+                    return Ok(());
                 }
             }
+            _ => {}
         }
     }
 }
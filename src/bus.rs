@@ -0,0 +1,66 @@
+// Typed messages between modules, queued while handling input and
+// delivered by `App` between frames.
+use std::{cell::RefCell, collections::VecDeque, fmt, rc::Rc};
+
+use crate::ui::notify::Level;
+
+#[derive(Clone, PartialEq)]
+pub enum Message {
+    // A concept was created or its definition/confidence changed.
+    ConceptChanged(String),
+    RelationAdded { from: String, relation_type: String, to: String },
+    EpisodeRecorded { outcome: String, summary: String },
+    // The user is talking about this concept; visible views may follow.
+    FocusConcept(String),
+    // Undo or redo rewrote stored data, e.g. "undo: rel jwt uses jws".
+    Reverted(String),
+    // Many concepts and relations at once, e.g. "12 note(s) from notes/".
+    Imported(String),
+    Notify(Level, String),
+}
+
+impl fmt::Display for Message {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Message::ConceptChanged(name) => write!(f, "concept '{}' changed", name),
+            Message::RelationAdded { from, relation_type, to } => {
+                write!(f, "relation {} --{}--> {}", from, relation_type, to)
+            }
+            Message::EpisodeRecorded { outcome, summary } => write!(f, "episode [{}] {}", outcome, summary),
+            Message::FocusConcept(name) => write!(f, "focus on '{}'", name),
+            Message::Reverted(what) => write!(f, "{}", what),
+            Message::Imported(what) => write!(f, "imported {}", what),
+            Message::Notify(_, text) => write!(f, "{}", text),
+        }
+    }
+}
+
+pub struct Envelope {
+    // Registry name of the publisher; it does not get its own messages back.
+    pub from: &'static str,
+    pub message: Message,
+}
+
+/// Cheap-to-clone publishing handle; each module gets one tagged with its name.
+#[derive(Clone, Default)]
+pub struct Bus {
+    queue: Rc<RefCell<VecDeque<Envelope>>>,
+    from: &'static str,
+}
+
+impl Bus {
+    /// A handle on the same queue that publishes as `from`.
+    pub fn tagged(&self, from: &'static str) -> Self {
+        Self { queue: Rc::clone(&self.queue), from }
+    }
+
+    pub fn publish(&self, message: Message) {
+        self.queue.borrow_mut().push_back(Envelope { from: self.from, message });
+    }
+
+    /// Everything queued so far. Messages published while these are being
+    /// delivered wait for the next frame.
+    pub fn drain(&self) -> Vec<Envelope> {
+        self.queue.borrow_mut().drain(..).collect()
+    }
+}
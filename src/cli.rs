@@ -0,0 +1,134 @@
+// Headless entry points: `mother-terminal <command> ...` runs without the TUI.
+use mother_terminal::bus::Bus;
+use mother_terminal::config::{Config, CONFIG_PATH};
+use mother_terminal::db::{self, Database};
+use mother_terminal::modules::dialog::{BatchOptions, Dialog};
+use mother_terminal::ui::notify::{Level, Notifier};
+
+use crate::http;
+
+const USAGE: &str = "usage: mother-terminal source [--yes] [--keep-going] <file>\n       mother-terminal serve [--port=N]\n       mother-terminal rpc";
+
+/// Returns the process exit code: 0 on success, 1 if any line failed,
+/// 2 for usage or setup errors.
+pub fn run(args: &[String]) -> i32 {
+    match args.first().map(String::as_str) {
+        Some("source") => source(&args[1..]),
+        Some("serve") => serve(&args[1..]),
+        Some("rpc") if args.len() == 1 => rpc(),
+        Some("-h" | "--help" | "help") => {
+            println!("{}", USAGE);
+            0
+        }
+        _ => {
+            eprintln!("{}", USAGE);
+            2
+        }
+    }
+}
+
+/// mother.json, with the audit operator set from it; None after printing why not.
+fn load_config() -> Option<Config> {
+    match Config::load(CONFIG_PATH) {
+        Ok(config) => {
+            db::set_operator(config.operator.as_deref());
+            Some(config)
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            None
+        }
+    }
+}
+
+fn source(args: &[String]) -> i32 {
+    let mut opts = BatchOptions::default();
+    let mut path = None;
+    for a in args {
+        match a.as_str() {
+            "--yes" => opts.confirm = true,
+            "--keep-going" => opts.keep_going = true,
+            flag if flag.starts_with("--") => {
+                eprintln!("unknown flag {}\n{}", flag, USAGE);
+                return 2;
+            }
+            p if path.is_none() => path = Some(p),
+            _ => {
+                eprintln!("{}", USAGE);
+                return 2;
+            }
+        }
+    }
+    let Some(path) = path else {
+        eprintln!("{}", USAGE);
+        return 2;
+    };
+
+    let Some(config) = load_config() else { return 2 };
+    let db = match Database::init(&config.db_path) {
+        Ok(db) => db,
+        Err(e) => {
+            eprintln!("cannot open {}: {}", config.db_path, e);
+            return 2;
+        }
+    };
+
+    let notifier = Notifier::default();
+    let mut dialog = Dialog::new(db, notifier.clone(), Bus::default(), &config);
+    let result = dialog.source(path, opts);
+
+    for n in notifier.history().iter().rev().filter(|n| n.level != Level::Info) {
+        eprintln!("{}", n.text);
+    }
+    match result {
+        Ok((summary, transcript)) => {
+            for line in &transcript {
+                println!("{}", line);
+            }
+            if summary.failed.is_empty() { 0 } else { 1 }
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            2
+        }
+    }
+}
+
+fn serve(args: &[String]) -> i32 {
+    let mut port = http::DEFAULT_PORT;
+    for a in args {
+        match a.strip_prefix("--port=").map(str::parse) {
+            Some(Ok(p)) => port = p,
+            _ => {
+                eprintln!("bad argument {}\n{}", a, USAGE);
+                return 2;
+            }
+        }
+    }
+    let Some(config) = load_config() else { return 2 };
+    match http::serve(&config, port) {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("{}", e);
+            2
+        }
+    }
+}
+
+#[cfg(unix)]
+fn rpc() -> i32 {
+    let Some(config) = load_config() else { return 2 };
+    match crate::rpc::serve(&config) {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("{}", e);
+            2
+        }
+    }
+}
+
+#[cfg(not(unix))]
+fn rpc() -> i32 {
+    eprintln!("rpc needs Unix domain sockets; use serve instead");
+    2
+}
@@ -0,0 +1,464 @@
+// Command-line grammar shared by the text screens:
+//
+//   line  := word flag* arg*
+//   flag  := --name | --name=value     a bare `--` ends flags
+//   arg   := bare | "quoted"          e.g. "json web token"
+//
+// Flags come before the first argument, so free text such as an episode
+// summary may contain `--`. Only double quotes group words, so apostrophes
+// in ordinary text are literal. A backslash makes the next character
+// literal, in or out of quotes (`\"`, `\\`, `\ `).
+//
+// Commands that end in free text (a definition, a summary) take it as typed,
+// from the raw input; a stray quote or backslash there is not an error.
+
+use std::collections::BTreeMap;
+
+/// One word of input; `pos` is its char offset, for error carets, and
+/// `end` the offset just past it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Token {
+    pub text: String,
+    pub pos: usize,
+    pub end: usize,
+    pub quoted: bool,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Flag {
+    pub name: String,
+    pub value: Option<String>,
+    pub pos: usize,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParseError {
+    pub pos: usize,
+    pub message: String,
+}
+
+impl ParseError {
+    pub fn new(pos: usize, message: impl Into<String>) -> Self {
+        Self { pos, message: message.into() }
+    }
+
+    /// The message, the input, and a caret under the offending position.
+    pub fn render(&self, input: &str) -> Vec<String> {
+        vec![
+            format!("Syntax error: {}", self.message),
+            format!("  {}", input),
+            format!("  {}^", " ".repeat(self.pos)),
+        ]
+    }
+}
+
+pub fn tokenize(input: &str) -> Result<Vec<Token>, ParseError> {
+    match tokenize_partial(input) {
+        (tokens, None) => Ok(tokens),
+        (_, Some(e)) => Err(e),
+    }
+}
+
+/// The tokens before the first error, and the error.
+fn tokenize_partial(input: &str) -> (Vec<Token>, Option<ParseError>) {
+    let mut tokens = Vec::new();
+    let mut chars = input.chars().enumerate().peekable();
+
+    while let Some(&(start, c)) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+            continue;
+        }
+        let mut text = String::new();
+        let mut end = start;
+        let mut quoted = false;
+        // Open quote position while inside one.
+        let mut open: Option<usize> = None;
+        while let Some(&(i, c)) = chars.peek() {
+            if open.is_none() && c.is_whitespace() {
+                break;
+            }
+            chars.next();
+            end = i + 1;
+            match c {
+                '\\' => match chars.next() {
+                    Some((j, next)) => {
+                        text.push(next);
+                        end = j + 1;
+                    }
+                    None => return (tokens, Some(ParseError::new(i, "nothing to escape after '\\'"))),
+                },
+                '"' if open.is_some() => open = None,
+                '"' => {
+                    open = Some(i);
+                    quoted = true;
+                }
+                c => text.push(c),
+            }
+        }
+        if let Some(at) = open {
+            return (tokens, Some(ParseError::new(at, "unterminated quote")));
+        }
+        tokens.push(Token { text, pos: start, end, quoted });
+    }
+    (tokens, None)
+}
+
+/// A parsed line: lowercased command word, positional args, and flags.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Command {
+    pub name: String,
+    pub pos: usize,
+    pub args: Vec<Token>,
+    pub flags: Vec<Flag>,
+    pub input: String,
+    // Char length of the input, for carets at the end of the line.
+    pub end: usize,
+    // The first tokenizing error; `args` stop before it. Only free-text
+    // commands may ignore it (see `complete`).
+    pub error: Option<ParseError>,
+}
+
+/// `Ok(None)` for a blank line; an error only if the command word itself
+/// can't be read.
+pub fn parse(input: &str) -> Result<Option<Command>, ParseError> {
+    let (tokens, error) = tokenize_partial(input);
+    let mut tokens = tokens.into_iter();
+    let Some(head) = tokens.next() else {
+        return match error {
+            Some(e) => Err(e),
+            None => Ok(None),
+        };
+    };
+
+    let mut args = Vec::new();
+    let mut flags = Vec::new();
+    let mut flags_done = false;
+    for t in tokens {
+        let flag = if t.quoted || flags_done { None } else { t.text.strip_prefix("--") };
+        match flag {
+            Some("") => flags_done = true,
+            Some(body) => {
+                let (name, value) = match body.split_once('=') {
+                    Some((n, v)) => (n, Some(v.to_string())),
+                    None => (body, None),
+                };
+                flags.push(Flag { name: name.to_lowercase(), value, pos: t.pos });
+            }
+            None => {
+                flags_done = true;
+                args.push(t);
+            }
+        }
+    }
+
+    Ok(Some(Command {
+        name: head.text.to_lowercase(),
+        pos: head.pos,
+        args,
+        flags,
+        input: input.to_string(),
+        end: input.chars().count(),
+        error,
+    }))
+}
+
+impl Command {
+    pub fn flag(&self, name: &str) -> Option<&Flag> {
+        self.flags.iter().find(|f| f.name == name)
+    }
+
+    /// Errors on the first flag not in `allowed`.
+    pub fn check_flags(&self, allowed: &[&str]) -> Result<(), ParseError> {
+        match self.flags.iter().find(|f| !allowed.contains(&f.name.as_str())) {
+            Some(f) if allowed.is_empty() => Err(ParseError::new(f.pos, format!("'{}' takes no flags", self.name))),
+            Some(f) => Err(ParseError::new(
+                f.pos,
+                format!("unknown flag --{} (expected --{})", f.name, allowed.join(", --")),
+            )),
+            None => Ok(()),
+        }
+    }
+
+    /// `--name=<value>` parsed as `T`, or `default` when the flag is absent.
+    pub fn flag_value<T: std::str::FromStr>(&self, name: &str, default: T) -> Result<T, ParseError> {
+        let Some(f) = self.flag(name) else { return Ok(default) };
+        match f.value.as_deref().map(str::parse) {
+            Some(Ok(v)) => Ok(v),
+            Some(Err(_)) => Err(ParseError::new(f.pos, format!("bad value for --{}", name))),
+            None => Err(ParseError::new(f.pos, format!("--{} needs a value: --{}=<value>", name, name))),
+        }
+    }
+
+    /// Args from `from` on, joined with single spaces.
+    pub fn rest(&self, from: usize) -> String {
+        self.args.iter().skip(from).map(|t| t.text.as_str()).collect::<Vec<_>>().join(" ")
+    }
+
+    /// Errors if part of the line couldn't be tokenized.
+    pub fn complete(&self) -> Result<(), ParseError> {
+        match &self.error {
+            Some(e) => Err(e.clone()),
+            None => Ok(()),
+        }
+    }
+
+    /// The input from arg `i` on, exactly as typed (quotes and escapes kept).
+    pub fn raw_from(&self, i: usize) -> &str {
+        match self.args.get(i) {
+            Some(t) => self.input[self.byte_at(t.pos)..].trim_end(),
+            None => "",
+        }
+    }
+
+    /// The input after arg `i`, exactly as typed and trimmed; it may hold
+    /// text that failed to tokenize.
+    pub fn raw_after(&self, i: usize) -> &str {
+        match self.args.get(i) {
+            Some(t) => self.input[self.byte_at(t.end)..].trim(),
+            None => "",
+        }
+    }
+
+    fn byte_at(&self, char_pos: usize) -> usize {
+        self.input.char_indices().nth(char_pos).map_or(self.input.len(), |(b, _)| b)
+    }
+
+    /// Position for "missing argument" errors: just past the line's end.
+    pub fn missing(&self, message: impl Into<String>) -> ParseError {
+        ParseError::new(self.end, message)
+    }
+}
+
+/// Runs a command on `T`; `R` is what success yields, e.g. DIALOG's actions.
+pub type Handler<T, R> = fn(&mut T, &Command) -> Result<R, ParseError>;
+
+/// Declarative description of one command run by screen `T`.
+pub struct CommandSpec<T: 'static, R: 'static> {
+    pub name: &'static str,
+    pub aliases: &'static [&'static str],
+    // Usage after the name, e.g. "[--confidence=X] <concept> is <definition>".
+    pub args: &'static str,
+    pub flags: &'static [&'static str],
+    // Ends in text taken as typed (`Command::raw_after`), where unbalanced
+    // quotes are allowed.
+    pub free_text: bool,
+    pub help: &'static str,
+    pub examples: &'static [&'static str],
+    pub run: Handler<T, R>,
+}
+
+impl<T, R> CommandSpec<T, R> {
+    pub fn usage(&self) -> String {
+        format!("{} {}", self.name, self.args).trim_end().to_string()
+    }
+}
+
+/// A screen's commands plus the operator's own aliases; help, usage lines
+/// and completion are all derived from the specs.
+pub struct Registry<T: 'static, R: 'static> {
+    specs: &'static [CommandSpec<T, R>],
+    // Alias name -> text it expands to, e.g. "ll" -> "list 100".
+    user: BTreeMap<String, String>,
+}
+
+impl<T, R> Registry<T, R> {
+    pub fn new(specs: &'static [CommandSpec<T, R>]) -> Self {
+        Self { specs, user: BTreeMap::new() }
+    }
+
+    /// Spec for a command name or one of its built-in aliases.
+    pub fn find(&self, word: &str) -> Option<&'static CommandSpec<T, R>> {
+        let word = word.to_lowercase();
+        self.specs.iter().find(|s| s.name == word || s.aliases.contains(&word.as_str()))
+    }
+
+    pub fn add_alias(&mut self, name: &str, expansion: &str) -> Result<(), String> {
+        let name = name.to_lowercase();
+        if name.is_empty() || name.chars().any(char::is_whitespace) || name.starts_with('-') {
+            return Err(format!("'{}' is not a valid alias name", name));
+        }
+        if self.find(&name).is_some() {
+            return Err(format!("'{}' is a built-in command", name));
+        }
+        if expansion.trim().is_empty() {
+            return Err(format!("alias '{}' needs a command to expand to", name));
+        }
+        self.user.insert(name, expansion.trim().to_string());
+        Ok(())
+    }
+
+    pub fn remove_alias(&mut self, name: &str) -> bool {
+        self.user.remove(&name.to_lowercase()).is_some()
+    }
+
+    pub fn aliases(&self) -> &BTreeMap<String, String> {
+        &self.user
+    }
+
+    /// Replaces a leading user alias with its expansion, once (aliases of
+    /// aliases are not expanded).
+    pub fn expand(&self, line: &str) -> String {
+        let trimmed = line.trim_start();
+        let word = trimmed.split_whitespace().next().unwrap_or("");
+        match self.user.get(&word.to_lowercase()) {
+            Some(expansion) => format!("{}{}", expansion, &trimmed[word.len()..]),
+            None => line.to_string(),
+        }
+    }
+
+    /// Command names and aliases starting with `prefix`, sorted.
+    pub fn complete(&self, prefix: &str) -> Vec<String> {
+        let prefix = prefix.to_lowercase();
+        let mut out: Vec<String> = self.specs
+            .iter()
+            .flat_map(|s| std::iter::once(s.name).chain(s.aliases.iter().copied()))
+            .map(str::to_string)
+            .chain(self.user.keys().cloned())
+            .filter(|w| w.starts_with(&prefix))
+            .collect();
+        out.sort();
+        out.dedup();
+        out
+    }
+
+    /// One line per command, for `help`.
+    pub fn summary(&self) -> Vec<String> {
+        let width = self.specs.iter().map(|s| s.usage().chars().count()).max().unwrap_or(0);
+        let mut out: Vec<String> = self.specs
+            .iter()
+            .map(|s| format!("  {:<width$}  {}", s.usage(), s.help, width = width))
+            .collect();
+        if !self.user.is_empty() {
+            out.push("  aliases:".into());
+            out.extend(self.user.iter().map(|(k, v)| format!("    {} = {}", k, v)));
+        }
+        out
+    }
+
+    /// Usage, aliases and examples for `help <word>`.
+    pub fn help(&self, word: &str) -> Option<Vec<String>> {
+        if let Some(expansion) = self.user.get(&word.to_lowercase()) {
+            return Some(vec![format!("  {} is an alias for: {}", word, expansion)]);
+        }
+        let spec = self.find(word)?;
+        let mut out = vec![format!("  {}", spec.usage()), format!("    {}", spec.help)];
+        if !spec.aliases.is_empty() {
+            out.push(format!("    aliases: {}", spec.aliases.join(", ")));
+        }
+        if !spec.examples.is_empty() {
+            out.push("    examples:".into());
+            out.extend(spec.examples.iter().map(|e| format!("      {}", e)));
+        }
+        Some(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn words(input: &str) -> Vec<String> {
+        tokenize(input).unwrap().into_iter().map(|t| t.text).collect()
+    }
+
+    fn parsed(input: &str) -> Command {
+        parse(input).unwrap().unwrap()
+    }
+
+    #[test]
+    fn quotes_group_words() {
+        assert_eq!(words(r#"show "json web token""#), ["show", "json web token"]);
+        assert_eq!(words(r#"rel a"b c"d e"#), ["rel", "ab cd", "e"]);
+        assert_eq!(words(r#"show """#), ["show", ""]);
+        // Apostrophes are ordinary characters.
+        assert_eq!(words("ep ok it's done"), ["ep", "ok", "it's", "done"]);
+
+        let tokens = tokenize(r#"show "a b" c"#).unwrap();
+        assert!(tokens[1].quoted && !tokens[2].quoted);
+        assert_eq!((tokens[1].pos, tokens[1].end), (5, 10));
+    }
+
+    #[test]
+    fn backslash_escapes_the_next_character() {
+        assert_eq!(words(r#"show a\ b \"q\" "x\"y" \\"#), ["show", "a b", "\"q\"", "x\"y", "\\"]);
+        let err = tokenize(r"show a\").unwrap_err();
+        assert_eq!(err, ParseError::new(6, "nothing to escape after '\\'"));
+        let err = tokenize(r#"show "abc"#).unwrap_err();
+        assert_eq!(err, ParseError::new(5, "unterminated quote"));
+    }
+
+    #[test]
+    fn flags_come_before_arguments() {
+        let cmd = parsed("Export --format=dot --around graph kb.dot --depth=2");
+        assert_eq!(cmd.name, "export");
+        let flags: Vec<_> = cmd.flags.iter().map(|f| (f.name.as_str(), f.value.as_deref())).collect();
+        assert_eq!(flags, [("format", Some("dot")), ("around", None)]);
+        let args: Vec<_> = cmd.args.iter().map(|t| t.text.as_str()).collect();
+        assert_eq!(args, ["graph", "kb.dot", "--depth=2"]);
+
+        // A quoted word is never a flag.
+        let cmd = parsed(r#"show "--help""#);
+        assert!(cmd.flags.is_empty());
+        assert_eq!(cmd.args[0].text, "--help");
+    }
+
+    #[test]
+    fn double_dash_ends_flags() {
+        let cmd = parsed("learn --confidence=0.5 -- --weird is odd");
+        assert_eq!(cmd.flags.len(), 1);
+        let args: Vec<_> = cmd.args.iter().map(|t| t.text.as_str()).collect();
+        assert_eq!(args, ["--weird", "is", "odd"]);
+    }
+
+    #[test]
+    fn flag_values_and_unknown_flags() {
+        let cmd = parsed("list --limit=x --other");
+        assert_eq!(cmd.flag_value("limit", 5usize).unwrap_err().message, "bad value for --limit");
+        assert_eq!(cmd.flag_value("missing", 5usize), Ok(5));
+        assert_eq!(cmd.flag_value::<usize>("other", 5).unwrap_err().pos, 15);
+        let err = cmd.check_flags(&["limit"]).unwrap_err();
+        assert_eq!(err, ParseError::new(15, "unknown flag --other (expected --limit)"));
+        assert_eq!(cmd.check_flags(&[]).unwrap_err().message, "'list' takes no flags");
+    }
+
+    #[test]
+    fn blank_lines_parse_to_nothing() {
+        assert_eq!(parse("   "), Ok(None));
+        assert_eq!(parse(r#""unterminated"#), Err(ParseError::new(0, "unterminated quote")));
+    }
+
+    #[test]
+    fn free_text_is_taken_as_typed() {
+        let input = r#"learn "json web token" is a  "signed"   token\n, 5" wide"#;
+        let cmd = parsed(input);
+        let stray = input.find("5\"").unwrap() + 1;
+        assert_eq!(cmd.error, Some(ParseError::new(stray, "unterminated quote")));
+        assert!(cmd.complete().is_err());
+        let is = cmd.args.iter().position(|t| t.text == "is").unwrap();
+        assert_eq!(cmd.raw_after(is), r#"a  "signed"   token\n, 5" wide"#);
+        assert_eq!(cmd.raw_after(9), "");
+
+        let cmd = parsed("alias ll  list   100 ");
+        assert_eq!(cmd.raw_from(1), "list   100");
+        assert_eq!(cmd.rest(1), "list 100");
+        assert!(cmd.complete().is_ok());
+    }
+
+    #[test]
+    fn positions_count_chars_not_bytes() {
+        let input = r#"show café "crème"#;
+        let err = tokenize(input).unwrap_err();
+        assert_eq!(err.pos, 10);
+        let lines = err.render(input);
+        assert_eq!(lines[1], format!("  {}", input));
+        // The caret sits under the quote, not nine bytes in.
+        assert_eq!(lines[2].chars().count(), lines[1].chars().position(|c| c == '"').unwrap() + 1);
+        assert!(lines[2].ends_with('^'));
+
+        let cmd = parsed("learn é is è  déf ");
+        assert_eq!((cmd.args[1].pos, cmd.args[1].end), (8, 10));
+        assert_eq!(cmd.raw_after(1), "è  déf");
+        assert_eq!(cmd.raw_from(2), "è  déf");
+    }
+}
@@ -0,0 +1,46 @@
+use std::{collections::BTreeMap, fs, io::ErrorKind};
+
+use serde::Deserialize;
+
+use crate::rdf::DEFAULT_BASE;
+
+pub const CONFIG_PATH: &str = "mother.json";
+
+/// Operator settings from `mother.json`; every field is optional.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub db_path: String,
+    // Seconds between Console dashboard refreshes.
+    pub refresh_secs: u64,
+    // DIALOG shorthands, e.g. "ll": "list 100".
+    pub aliases: BTreeMap<String, String>,
+    // Name written to the audit log; MOTHER_OPERATOR overrides it, and the
+    // login name is used when neither is set.
+    pub operator: Option<String>,
+    // Base IRI for RDF export and import; see `rdf`.
+    pub rdf_base: String,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            db_path: "mother.db".into(),
+            refresh_secs: 2,
+            aliases: BTreeMap::new(),
+            operator: None,
+            rdf_base: DEFAULT_BASE.into(),
+        }
+    }
+}
+
+impl Config {
+    /// Reads `path`; a missing file yields the defaults.
+    pub fn load(path: &str) -> Result<Self, String> {
+        match fs::read_to_string(path) {
+            Ok(text) => serde_json::from_str(&text).map_err(|e| format!("{}: {}", path, e)),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(format!("{}: {}", path, e)),
+        }
+    }
+}
@@ -0,0 +1,162 @@
+// Append-only audit trail: who changed what, when, and with which command.
+// Written in the same transaction as the change (see `history::record`);
+// triggers reject any UPDATE or DELETE of existing rows.
+use std::sync::OnceLock;
+
+use rusqlite::{params, Result, Row, Transaction};
+use serde::Serialize;
+use time::OffsetDateTime;
+
+use super::{history::Image, Database};
+
+/// Who is writing: one per process, shared by every connection.
+pub(super) struct Actor {
+    pub(super) operator: String,
+    pub(super) session: String,
+}
+
+static ACTOR: OnceLock<Actor> = OnceLock::new();
+
+/// Fixes the operator for this process: `MOTHER_OPERATOR`, then the
+/// configured name, then the login name. Later calls have no effect.
+pub fn set_operator(configured: Option<&str>) {
+    ACTOR.get_or_init(|| Actor { operator: operator_from(configured), session: new_session() });
+}
+
+pub(super) fn actor() -> &'static Actor {
+    ACTOR.get_or_init(|| Actor { operator: operator_from(None), session: new_session() })
+}
+
+fn operator_from(configured: Option<&str>) -> String {
+    let env = |name| std::env::var(name).ok().filter(|v: &String| !v.trim().is_empty());
+    env("MOTHER_OPERATOR")
+        .or_else(|| configured.filter(|c| !c.trim().is_empty()).map(str::to_string))
+        .or_else(|| env("USER"))
+        .or_else(|| env("USERNAME"))
+        .unwrap_or_else(|| "unknown".into())
+}
+
+// e.g. "1792334801-4242": start time and process id.
+fn new_session() -> String {
+    format!("{}-{}", OffsetDateTime::now_utc().unix_timestamp(), std::process::id())
+}
+
+/// One row of `audit_log`.
+#[derive(Debug, Clone, Serialize)]
+pub struct AuditEntry {
+    pub id: i64,
+    pub at: String,
+    pub operator: String,
+    pub session: String,
+    // The command as typed, or "" for writes with no command line.
+    pub command: String,
+    // What changed, e.g. "update jwt" or "undo: rel jwt uses jws".
+    pub change: String,
+    pub concepts: Vec<String>,
+    pub before: Option<String>,
+    pub after: Option<String>,
+}
+
+fn concepts_of(img: Option<&Image>) -> Vec<&str> {
+    match img {
+        Some(Image::Concept { name, .. }) => vec![name],
+        Some(Image::Relation { from, to, .. }) => vec![from, to],
+        Some(Image::Episode { .. }) | None => Vec::new(),
+    }
+}
+
+pub(super) fn append(
+    db: &Database,
+    tx: &Transaction,
+    change: &str,
+    before: Option<&Image>,
+    after: Option<&Image>,
+) -> Result<()> {
+    let json = |img: Option<&Image>| img.map(|i| serde_json::to_string(i).expect("images always serialize"));
+    let mut concepts = concepts_of(before);
+    concepts.extend(concepts_of(after));
+    concepts.sort();
+    concepts.dedup();
+    let actor = actor();
+    tx.execute(
+        "INSERT INTO audit_log (at, operator, session, command, change, concepts, before, after)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        params![
+            Database::now(),
+            actor.operator,
+            actor.session,
+            db.command.borrow().as_str(),
+            change,
+            serde_json::to_string(&concepts).expect("names always serialize"),
+            json(before),
+            json(after),
+        ],
+    )?;
+    Ok(())
+}
+
+impl Database {
+    /// Command text recorded with this connection's writes until the next call.
+    pub fn set_command(&self, command: &str) {
+        *self.command.borrow_mut() = command.to_string();
+    }
+
+    /// Newest first, optionally only rows touching `concept` or written by `operator`.
+    pub fn list_audit(&self, concept: Option<&str>, operator: Option<&str>, limit: usize) -> Result<Vec<AuditEntry>> {
+        let mut stmt = self.conn.prepare(
+            "
+            SELECT id, at, operator, session, command, change, concepts, before, after
+            FROM audit_log
+            WHERE (?1 IS NULL OR EXISTS (SELECT 1 FROM json_each(audit_log.concepts) WHERE value = ?1))
+              AND (?2 IS NULL OR operator = ?2)
+            ORDER BY id DESC
+            LIMIT ?3
+            ",
+        )?;
+        let rows = stmt.query_map(params![concept, operator, limit as i64], entry)?;
+        let mut out = Vec::new();
+        for r in rows {
+            out.push(r?);
+        }
+        Ok(out)
+    }
+
+    /// Id of the newest entry, or 0; a starting point for `audit_after`.
+    pub fn latest_audit_id(&self) -> Result<i64> {
+        self.conn.query_row("SELECT COALESCE(MAX(id), 0) FROM audit_log", [], |row| row.get(0))
+    }
+
+    /// Entries newer than `id`, oldest first, from any connection or process.
+    pub fn audit_after(&self, id: i64, limit: usize) -> Result<Vec<AuditEntry>> {
+        let mut stmt = self.conn.prepare(
+            "
+            SELECT id, at, operator, session, command, change, concepts, before, after
+            FROM audit_log
+            WHERE id > ?1
+            ORDER BY id ASC
+            LIMIT ?2
+            ",
+        )?;
+        let rows = stmt.query_map(params![id, limit as i64], entry)?;
+        let mut out = Vec::new();
+        for r in rows {
+            out.push(r?);
+        }
+        Ok(out)
+    }
+}
+
+fn entry(row: &Row) -> Result<AuditEntry> {
+    let concepts: String = row.get(6)?;
+    Ok(AuditEntry {
+        id: row.get(0)?,
+        at: row.get(1)?,
+        operator: row.get(2)?,
+        session: row.get(3)?,
+        command: row.get(4)?,
+        change: row.get(5)?,
+        concepts: serde_json::from_str(&concepts).unwrap_or_default(),
+        before: row.get(7)?,
+        after: row.get(8)?,
+    })
+}
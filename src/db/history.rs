@@ -0,0 +1,185 @@
+// Undo/redo log. Every mutation made through `Database` stores the row
+// before and after the change in `undo_log`, in the same transaction.
+// Entries carry the audit session, and undo/redo only step through the
+// current session's own: the TUI, `serve`, `rpc` and other operators may
+// share the file without reverting each other's writes. A step is also
+// refused when the row no longer holds what the entry left there, rather
+// than overwrite a later change.
+use rusqlite::{params, OptionalExtension, Result, Transaction};
+use serde::{Deserialize, Serialize};
+
+use super::{audit, Database};
+
+// Oldest entries beyond this are dropped.
+const MAX_ENTRIES: i64 = 1000;
+
+/// A full row, as JSON in `undo_log.before` / `undo_log.after`.
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub(super) enum Image {
+    Concept { name: String, definition: String, confidence: f64, created_at: String },
+    Relation { from: String, relation_type: String, to: String, created_at: String },
+    Episode { id: i64, captured_at: String, outcome: String, summary: String },
+}
+
+pub(super) fn concept_image(tx: &Transaction, name: &str) -> Result<Option<Image>> {
+    tx.query_row(
+        "SELECT name, definition, confidence, created_at FROM concepts WHERE name = ?1",
+        params![name],
+        |row| {
+            Ok(Image::Concept {
+                name: row.get(0)?,
+                definition: row.get(1)?,
+                confidence: row.get(2)?,
+                created_at: row.get(3)?,
+            })
+        },
+    )
+    .optional()
+}
+
+/// Appends an entry, and one to the audit trail; a new change discards
+/// anything that could be redone.
+pub(super) fn record(
+    db: &Database,
+    tx: &Transaction,
+    label: &str,
+    before: Option<&Image>,
+    after: Option<&Image>,
+) -> Result<()> {
+    audit::append(db, tx, label, before, after)?;
+    let json = |img: Option<&Image>| img.map(|i| serde_json::to_string(i).expect("images always serialize"));
+    let actor = audit::actor();
+    tx.execute("DELETE FROM undo_log WHERE undone = 1 AND session = ?1", params![actor.session])?;
+    tx.execute(
+        "INSERT INTO undo_log (at, label, before, after, undone, session, operator) VALUES (?1, ?2, ?3, ?4, 0, ?5, ?6)",
+        params![Database::now(), label, json(before), json(after), actor.session, actor.operator],
+    )?;
+    tx.execute(
+        "DELETE FROM undo_log WHERE id <= (SELECT MAX(id) FROM undo_log) - ?1",
+        params![MAX_ENTRIES],
+    )?;
+    Ok(())
+}
+
+/// The row `like` describes, as it is now.
+fn current_image(tx: &Transaction, like: &Image) -> Result<Option<Image>> {
+    match like {
+        Image::Concept { name, .. } => concept_image(tx, name),
+        Image::Relation { from, relation_type, to, .. } => tx
+            .query_row(
+                "SELECT created_at FROM concept_relations
+                 WHERE from_concept = ?1 AND relation_type = ?2 AND to_concept = ?3",
+                params![from, relation_type, to],
+                |row| {
+                    Ok(Image::Relation {
+                        from: from.clone(),
+                        relation_type: relation_type.clone(),
+                        to: to.clone(),
+                        created_at: row.get(0)?,
+                    })
+                },
+            )
+            .optional(),
+        Image::Episode { id, .. } => tx
+            .query_row(
+                "SELECT captured_at, outcome, summary FROM episodes WHERE id = ?1",
+                params![id],
+                |row| Ok(Image::Episode { id: *id, captured_at: row.get(0)?, outcome: row.get(1)?, summary: row.get(2)? }),
+            )
+            .optional(),
+    }
+}
+
+/// Makes the database hold `target` where it now holds `current`.
+fn restore(tx: &Transaction, current: Option<&Image>, target: Option<&Image>) -> Result<()> {
+    match (current, target) {
+        (_, Some(Image::Concept { name, definition, confidence, created_at })) => {
+            tx.execute(
+                "INSERT INTO concepts (name, definition, confidence, created_at) VALUES (?1, ?2, ?3, ?4)
+                 ON CONFLICT(name) DO UPDATE SET
+                   definition = excluded.definition,
+                   confidence = excluded.confidence,
+                   created_at = excluded.created_at",
+                params![name, definition, confidence, created_at],
+            )?;
+        }
+        (_, Some(Image::Relation { from, relation_type, to, created_at })) => {
+            tx.execute(
+                "INSERT OR IGNORE INTO concept_relations (from_concept, relation_type, to_concept, created_at)
+                 VALUES (?1, ?2, ?3, ?4)",
+                params![from, relation_type, to, created_at],
+            )?;
+        }
+        (_, Some(Image::Episode { id, captured_at, outcome, summary })) => {
+            tx.execute(
+                "INSERT OR REPLACE INTO episodes (id, captured_at, outcome, summary) VALUES (?1, ?2, ?3, ?4)",
+                params![id, captured_at, outcome, summary],
+            )?;
+        }
+        (Some(Image::Concept { name, .. }), None) => {
+            tx.execute("DELETE FROM concepts WHERE name = ?1", params![name])?;
+        }
+        (Some(Image::Relation { from, relation_type, to, .. }), None) => {
+            tx.execute(
+                "DELETE FROM concept_relations WHERE from_concept = ?1 AND relation_type = ?2 AND to_concept = ?3",
+                params![from, relation_type, to],
+            )?;
+        }
+        (Some(Image::Episode { id, .. }), None) => {
+            tx.execute("DELETE FROM episodes WHERE id = ?1", params![id])?;
+        }
+        (None, None) => {}
+    }
+    Ok(())
+}
+
+fn parse(json: Option<String>) -> Result<Option<Image>> {
+    json.map(|j| {
+        serde_json::from_str(&j).map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))
+    })
+    .transpose()
+}
+
+impl Database {
+    /// Reverts this session's newest change; returns its label, or None if
+    /// there is nothing to undo.
+    pub fn undo(&self) -> Result<Option<String>> {
+        self.step(
+            "SELECT id, label, before, after FROM undo_log WHERE undone = 0 AND session = ?1 ORDER BY id DESC LIMIT 1",
+            true,
+        )
+    }
+
+    /// Re-applies this session's most recently undone change.
+    pub fn redo(&self) -> Result<Option<String>> {
+        self.step(
+            "SELECT id, label, before, after FROM undo_log WHERE undone = 1 AND session = ?1 ORDER BY id ASC LIMIT 1",
+            false,
+        )
+    }
+
+    fn step(&self, pick: &str, undo: bool) -> Result<Option<String>> {
+        let tx = self.conn.unchecked_transaction()?;
+        let entry = tx
+            .query_row(pick, params![audit::actor().session], |row| {
+                Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?, row.get(2)?, row.get(3)?))
+            })
+            .optional()?;
+        let Some((id, label, before, after)) = entry else { return Ok(None) };
+        let (before, after) = (parse(before)?, parse(after)?);
+        let (from, to, verb) = if undo { (&after, &before, "undo") } else { (&before, &after, "redo") };
+        let like = from.as_ref().or(to.as_ref());
+        if let Some(like) = like
+            && current_image(&tx, like)? != *from
+        {
+            let why = format!("cannot {} '{}': it was changed since; {}ing would lose that change", verb, label, verb);
+            return Err(rusqlite::Error::ToSqlConversionFailure(why.into()));
+        }
+        restore(&tx, from.as_ref(), to.as_ref())?;
+        audit::append(self, &tx, &format!("{}: {}", verb, label), from.as_ref(), to.as_ref())?;
+        tx.execute("UPDATE undo_log SET undone = ?1 WHERE id = ?2", params![undo, id])?;
+        tx.commit()?;
+        Ok(Some(label))
+    }
+}
@@ -1,11 +1,29 @@
-use rusqlite::{params, Connection, Result};
-use time::OffsetDateTime;
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet, VecDeque},
+};
+
+use rusqlite::{params, Connection, OptionalExtension, Result};
+use serde::Serialize;
+use time::{Duration, OffsetDateTime};
+
+mod audit;
+mod history;
+
+pub use audit::set_operator;
+use history::Image;
+
+/// Stored in `PRAGMA user_version`; bump when the schema changes.
+pub const SCHEMA_VERSION: i64 = 5;
 
 pub struct Database {
     conn: Connection,
+    path: String,
+    // Command text for the audit log; see `set_command`.
+    command: RefCell<String>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct Concept {
     pub id: i64,
     pub name: String,
@@ -14,7 +32,7 @@ pub struct Concept {
     pub created_at: String,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct Relation {
     pub id: i64,
     pub from: String,
@@ -23,7 +41,7 @@ pub struct Relation {
     pub created_at: String,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct Episode {
     pub id: i64,
     pub captured_at: String,
@@ -31,9 +49,35 @@ pub struct Episode {
     pub summary: String,
 }
 
+#[derive(Debug, Clone, Default)]
+pub struct Stats {
+    pub concepts: i64,
+    pub relations: i64,
+    pub episodes: i64,
+    // Relations whose endpoints have no concept record.
+    pub dangling_relations: i64,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct OutcomeCounts {
+    pub ok: i64,
+    pub fail: i64,
+    pub note: i64,
+}
+
+/// One row of the combined concept/relation/episode activity feed.
+#[derive(Debug, Clone)]
+pub struct Activity {
+    pub at: String,
+    pub kind: String, // "concept" | "relation" | "episode"
+    pub text: String,
+}
+
 impl Database {
     pub fn init(path: &str) -> Result<Self> {
         let conn = Connection::open(path)?;
+        // The TUI, scripts and the API server may share the file.
+        conn.busy_timeout(std::time::Duration::from_secs(2))?;
 
         conn.execute_batch(
             "
@@ -60,20 +104,165 @@ impl Database {
               outcome TEXT NOT NULL,
               summary TEXT NOT NULL
             );
+
+            -- v2: before/after JSON images of each change, for undo/redo
+            CREATE TABLE IF NOT EXISTS undo_log (
+              id INTEGER PRIMARY KEY AUTOINCREMENT,
+              at TEXT NOT NULL,
+              label TEXT NOT NULL,
+              before TEXT,
+              after TEXT,
+              undone INTEGER NOT NULL DEFAULT 0,
+              session TEXT NOT NULL DEFAULT '',
+              operator TEXT NOT NULL DEFAULT ''
+            );
+
+            -- v3: append-only audit trail; concepts is a JSON array of names
+            CREATE TABLE IF NOT EXISTS audit_log (
+              id INTEGER PRIMARY KEY AUTOINCREMENT,
+              at TEXT NOT NULL,
+              operator TEXT NOT NULL,
+              session TEXT NOT NULL,
+              command TEXT NOT NULL,
+              change TEXT NOT NULL,
+              concepts TEXT NOT NULL,
+              before TEXT,
+              after TEXT
+            );
+
+            CREATE TRIGGER IF NOT EXISTS audit_log_no_update BEFORE UPDATE ON audit_log
+            BEGIN SELECT RAISE(ABORT, 'audit_log is append-only'); END;
+
+            CREATE TRIGGER IF NOT EXISTS audit_log_no_delete BEFORE DELETE ON audit_log
+            BEGIN SELECT RAISE(ABORT, 'audit_log is append-only'); END;
+
+            -- v4: content hash of each Markdown note already imported
+            CREATE TABLE IF NOT EXISTS note_imports (
+              path TEXT PRIMARY KEY,
+              concept TEXT NOT NULL,
+              hash TEXT NOT NULL,
+              imported_at TEXT NOT NULL
+            );
             "
         )?;
+        // v5: undo_log entries belong to the audit session that wrote them.
+        // Entries from before v5 have no session, so nobody can undo them.
+        let has_session: bool = conn.query_row(
+            "SELECT EXISTS (SELECT 1 FROM pragma_table_info('undo_log') WHERE name = 'session')",
+            [],
+            |row| row.get(0),
+        )?;
+        if !has_session {
+            conn.execute_batch(
+                "
+                ALTER TABLE undo_log ADD COLUMN session TEXT NOT NULL DEFAULT '';
+                ALTER TABLE undo_log ADD COLUMN operator TEXT NOT NULL DEFAULT '';
+                ",
+            )?;
+        }
+        conn.pragma_update(None, "user_version", SCHEMA_VERSION)?;
+
+        Ok(Self { conn, path: path.to_string(), command: RefCell::new(String::new()) })
+    }
 
-        Ok(Self { conn })
+    pub fn path(&self) -> &str {
+        &self.path
     }
 
     fn now() -> String {
         OffsetDateTime::now_utc().to_string()
     }
 
+    /// Timestamp `days` ago, comparable with stored `created_at`/`captured_at`.
+    fn days_ago(days: i64) -> String {
+        (OffsetDateTime::now_utc() - Duration::days(days)).to_string()
+    }
+
+    // --- System ---
+    pub fn schema_version(&self) -> Result<i64> {
+        self.conn.query_row("PRAGMA user_version", [], |row| row.get(0))
+    }
+
+    pub fn stats(&self) -> Result<Stats> {
+        self.conn.query_row(
+            "
+            SELECT
+              (SELECT COUNT(*) FROM concepts),
+              (SELECT COUNT(*) FROM concept_relations),
+              (SELECT COUNT(*) FROM episodes),
+              (SELECT COUNT(*) FROM concept_relations r
+                 WHERE r.from_concept NOT IN (SELECT name FROM concepts)
+                    OR r.to_concept NOT IN (SELECT name FROM concepts))
+            ",
+            [],
+            |row| {
+                Ok(Stats {
+                    concepts: row.get(0)?,
+                    relations: row.get(1)?,
+                    episodes: row.get(2)?,
+                    dangling_relations: row.get(3)?,
+                })
+            },
+        )
+    }
+
+    pub fn vacuum(&self) -> Result<()> {
+        self.conn.execute_batch("VACUUM")
+    }
+
+    /// Rows of `PRAGMA integrity_check`; a healthy database yields just "ok".
+    pub fn integrity_check(&self) -> Result<Vec<String>> {
+        let mut stmt = self.conn.prepare("PRAGMA integrity_check")?;
+        let rows = stmt.query_map([], |row| row.get(0))?;
+        let mut out = Vec::new();
+        for r in rows {
+            out.push(r?);
+        }
+        Ok(out)
+    }
+
+    /// Writes a consistent copy of the database to `dest`, which must not exist.
+    pub fn backup_to(&self, dest: &str) -> Result<()> {
+        self.conn.execute("VACUUM INTO ?1", params![dest])?;
+        Ok(())
+    }
+
+    pub fn recent_activity(&self, limit: usize) -> Result<Vec<Activity>> {
+        let mut stmt = self.conn.prepare(
+            "
+            SELECT at, kind, text FROM (
+              SELECT created_at AS at, 'concept' AS kind, name AS text FROM concepts
+              UNION ALL
+              SELECT created_at, 'relation',
+                     from_concept || ' --' || relation_type || '--> ' || to_concept
+              FROM concept_relations
+              UNION ALL
+              SELECT captured_at, 'episode', '[' || outcome || '] ' || summary FROM episodes
+            )
+            ORDER BY at DESC
+            LIMIT ?1
+            "
+        )?;
+        let rows = stmt.query_map(params![limit as i64], |row| {
+            Ok(Activity {
+                at: row.get(0)?,
+                kind: row.get(1)?,
+                text: row.get(2)?,
+            })
+        })?;
+        let mut out = Vec::new();
+        for r in rows {
+            out.push(r?);
+        }
+        Ok(out)
+    }
+
     // --- Concepts ---
     pub fn upsert_concept(&self, name: &str, definition: &str, confidence: f64) -> Result<()> {
         let now = Self::now();
-        self.conn.execute(
+        let tx = self.conn.unchecked_transaction()?;
+        let before = history::concept_image(&tx, name)?;
+        tx.execute(
             "
             INSERT INTO concepts (name, definition, confidence, created_at)
             VALUES (?1, ?2, ?3, ?4)
@@ -83,7 +272,10 @@ impl Database {
             ",
             params![name, definition, confidence, now],
         )?;
-        Ok(())
+        let after = history::concept_image(&tx, name)?;
+        let verb = if before.is_some() { "update" } else { "learn" };
+        history::record(self, &tx, &format!("{} {}", verb, name), before.as_ref(), after.as_ref())?;
+        tx.commit()
     }
 
     pub fn get_concept(&self, name: &str) -> Result<Option<Concept>> {
@@ -142,10 +334,115 @@ impl Database {
         Ok(out)
     }
 
+    pub fn concept_confidences(&self) -> Result<HashMap<String, f64>> {
+        let mut stmt = self.conn.prepare("SELECT name, confidence FROM concepts")?;
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        let mut out = HashMap::new();
+        for r in rows {
+            let (name, conf) = r?;
+            out.insert(name, conf);
+        }
+        Ok(out)
+    }
+
+    /// Names of stored concepts that occur in `text` (case-insensitive).
+    pub fn concepts_mentioned_in(&self, text: &str) -> Result<Vec<String>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT name FROM concepts WHERE instr(lower(?1), lower(name)) > 0 ORDER BY name ASC"
+        )?;
+        let rows = stmt.query_map(params![text], |row| row.get(0))?;
+        let mut out = Vec::new();
+        for r in rows {
+            out.push(r?);
+        }
+        Ok(out)
+    }
+
+    /// Concepts whose name or definition contains `query` (case-insensitive),
+    /// name matches first.
+    pub fn search_concepts(&self, query: &str, limit: usize) -> Result<Vec<Concept>> {
+        let mut stmt = self.conn.prepare(
+            "
+            SELECT id, name, definition, confidence, created_at
+            FROM concepts
+            WHERE instr(lower(name), lower(?1)) > 0 OR instr(lower(definition), lower(?1)) > 0
+            ORDER BY instr(lower(name), lower(?1)) = 0, name ASC
+            LIMIT ?2
+            "
+        )?;
+        let rows = stmt.query_map(params![query, limit as i64], |row| {
+            Ok(Concept {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                definition: row.get(2)?,
+                confidence: row.get(3)?,
+                created_at: row.get(4)?,
+            })
+        })?;
+        let mut out = Vec::new();
+        for r in rows {
+            out.push(r?);
+        }
+        Ok(out)
+    }
+
+    /// Fewest relations linking `from` to `to`, in either direction, at most
+    /// `max_hops` long. `Some(vec![])` when they are the same concept.
+    pub fn find_path(&self, from: &str, to: &str, max_hops: usize) -> Result<Option<Vec<Relation>>> {
+        // Concept -> (relation that reached it, previous concept).
+        let mut came_from: HashMap<String, (Relation, String)> = HashMap::new();
+        let mut visited = HashSet::from([from.to_string()]);
+        let mut queue = VecDeque::from([(from.to_string(), 0)]);
+
+        while let Some((node, hops)) = queue.pop_front() {
+            if node == to {
+                let mut path = Vec::new();
+                let mut at = node;
+                while let Some((rel, prev)) = came_from.remove(&at) {
+                    path.push(rel);
+                    at = prev;
+                }
+                path.reverse();
+                return Ok(Some(path));
+            }
+            if hops == max_hops {
+                continue;
+            }
+            for rel in self.list_relations_for(&node, 500)? {
+                let next = if rel.from == node { rel.to.clone() } else { rel.from.clone() };
+                if visited.insert(next.clone()) {
+                    came_from.insert(next.clone(), (rel, node.clone()));
+                    queue.push_back((next, hops + 1));
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    // --- Note imports ---
+    /// Hash recorded by the last import of the note at `path`.
+    pub fn note_import_hash(&self, path: &str) -> Result<Option<String>> {
+        self.conn
+            .query_row("SELECT hash FROM note_imports WHERE path = ?1", params![path], |row| row.get(0))
+            .optional()
+    }
+
+    pub fn record_note_import(&self, path: &str, concept: &str, hash: &str) -> Result<()> {
+        self.conn.execute(
+            "INSERT INTO note_imports (path, concept, hash, imported_at) VALUES (?1, ?2, ?3, ?4)
+             ON CONFLICT(path) DO UPDATE SET
+               concept = excluded.concept, hash = excluded.hash, imported_at = excluded.imported_at",
+            params![path, concept, hash, Self::now()],
+        )?;
+        Ok(())
+    }
+
     // --- Relations ---
-    pub fn upsert_relation(&self, from: &str, relation_type: &str, to: &str) -> Result<()> {
+    /// Returns false if the relation already existed.
+    pub fn upsert_relation(&self, from: &str, relation_type: &str, to: &str) -> Result<bool> {
         let now = Self::now();
-        self.conn.execute(
+        let tx = self.conn.unchecked_transaction()?;
+        let inserted = tx.execute(
             "
             INSERT INTO concept_relations (from_concept, relation_type, to_concept, created_at)
             VALUES (?1, ?2, ?3, ?4)
@@ -153,7 +450,17 @@ impl Database {
             ",
             params![from, relation_type, to, now],
         )?;
-        Ok(())
+        if inserted > 0 {
+            let after = Image::Relation {
+                from: from.to_string(),
+                relation_type: relation_type.to_string(),
+                to: to.to_string(),
+                created_at: now,
+            };
+            history::record(self, &tx, &format!("rel {} {} {}", from, relation_type, to), None, Some(&after))?;
+        }
+        tx.commit()?;
+        Ok(inserted > 0)
     }
 
     pub fn list_relations_for(&self, concept: &str, limit: usize) -> Result<Vec<Relation>> {
@@ -184,14 +491,61 @@ impl Database {
         Ok(out)
     }
 
+    /// Every relation, oldest first.
+    pub fn list_all_relations(&self) -> Result<Vec<Relation>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, from_concept, relation_type, to_concept, created_at FROM concept_relations ORDER BY id ASC"
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok(Relation {
+                id: row.get(0)?,
+                from: row.get(1)?,
+                relation_type: row.get(2)?,
+                to: row.get(3)?,
+                created_at: row.get(4)?,
+            })
+        })?;
+        let mut out = Vec::new();
+        for r in rows {
+            out.push(r?);
+        }
+        Ok(out)
+    }
+
+    /// Distinct relation types with how many relations use each.
+    pub fn list_relation_types(&self) -> Result<Vec<(String, usize)>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT relation_type, COUNT(*) FROM concept_relations
+             GROUP BY relation_type
+             ORDER BY relation_type ASC"
+        )?;
+        let rows = stmt.query_map([], |row| {
+            let n: i64 = row.get(1)?;
+            Ok((row.get(0)?, n as usize))
+        })?;
+        let mut out = Vec::new();
+        for r in rows {
+            out.push(r?);
+        }
+        Ok(out)
+    }
+
     // --- Episodes (experience) ---
     pub fn add_episode(&self, outcome: &str, summary: &str) -> Result<()> {
         let now = Self::now();
-        self.conn.execute(
+        let tx = self.conn.unchecked_transaction()?;
+        tx.execute(
             "INSERT INTO episodes (captured_at, outcome, summary) VALUES (?1, ?2, ?3)",
             params![now, outcome, summary],
         )?;
-        Ok(())
+        let after = Image::Episode {
+            id: tx.last_insert_rowid(),
+            captured_at: now,
+            outcome: outcome.to_string(),
+            summary: summary.to_string(),
+        };
+        history::record(self, &tx, &format!("ep {} {}", outcome, summary), None, Some(&after))?;
+        tx.commit()
     }
 
     pub fn list_episodes(&self, limit: usize) -> Result<Vec<Episode>> {
@@ -217,4 +571,77 @@ impl Database {
         }
         Ok(out)
     }
+
+    /// Episodes captured between two "YYYY-MM-DD" days (inclusive, either
+    /// bound optional), oldest first.
+    pub fn list_episodes_between(&self, from: Option<&str>, to: Option<&str>) -> Result<Vec<Episode>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, captured_at, outcome, summary
+             FROM episodes
+             WHERE (?1 IS NULL OR substr(captured_at, 1, 10) >= ?1)
+               AND (?2 IS NULL OR substr(captured_at, 1, 10) <= ?2)
+             ORDER BY captured_at ASC, id ASC"
+        )?;
+
+        let rows = stmt.query_map(params![from, to], |row| {
+            Ok(Episode {
+                id: row.get(0)?,
+                captured_at: row.get(1)?,
+                outcome: row.get(2)?,
+                summary: row.get(3)?,
+            })
+        })?;
+
+        let mut out = Vec::new();
+        for r in rows {
+            out.push(r?);
+        }
+        Ok(out)
+    }
+
+    /// Episodes whose summary mentions `name` (case-insensitive), newest first.
+    pub fn episodes_mentioning(&self, name: &str, limit: usize) -> Result<Vec<Episode>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, captured_at, outcome, summary
+             FROM episodes
+             WHERE instr(lower(summary), lower(?1)) > 0
+             ORDER BY id DESC
+             LIMIT ?2"
+        )?;
+
+        let rows = stmt.query_map(params![name, limit as i64], |row| {
+            Ok(Episode {
+                id: row.get(0)?,
+                captured_at: row.get(1)?,
+                outcome: row.get(2)?,
+                summary: row.get(3)?,
+            })
+        })?;
+
+        let mut out = Vec::new();
+        for r in rows {
+            out.push(r?);
+        }
+        Ok(out)
+    }
+
+    pub fn outcome_counts(&self, days: i64) -> Result<OutcomeCounts> {
+        let mut stmt = self.conn.prepare(
+            "SELECT outcome, COUNT(*) FROM episodes WHERE captured_at >= ?1 GROUP BY outcome"
+        )?;
+        let rows = stmt.query_map(params![Self::days_ago(days)], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?))
+        })?;
+        let mut out = OutcomeCounts::default();
+        for r in rows {
+            let (outcome, n) = r?;
+            match outcome.as_str() {
+                "ok" => out.ok = n,
+                "fail" => out.fail = n,
+                "note" => out.note = n,
+                _ => {}
+            }
+        }
+        Ok(out)
+    }
 }
@@ -0,0 +1,301 @@
+// Diagrams of the concept graph: Graphviz DOT, Mermaid flowcharts and
+// GraphML. Relation types become edge labels; node colour (and border, for
+// DOT) follows the concept's confidence band.
+use std::collections::{BTreeMap, HashSet};
+
+use rusqlite::Result;
+
+use crate::db::{Database, Relation};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GraphFormat {
+    Dot,
+    Mermaid,
+    GraphMl,
+}
+
+impl GraphFormat {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "dot" | "gv" | "graphviz" => Some(Self::Dot),
+            "mermaid" | "mmd" => Some(Self::Mermaid),
+            "graphml" => Some(Self::GraphMl),
+            _ => None,
+        }
+    }
+
+    /// Guessed from the file extension: .dot/.gv, .mmd/.mermaid, .graphml.
+    pub fn from_path(path: &str) -> Option<Self> {
+        let ext = path.rsplit_once('.')?.1;
+        if ext.eq_ignore_ascii_case("mermaid") { Some(Self::Mermaid) } else { Self::from_name(ext) }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Dot => "dot",
+            Self::Mermaid => "mermaid",
+            Self::GraphMl => "graphml",
+        }
+    }
+}
+
+/// Confidence band of a node; `Unknown` for relation endpoints with no
+/// concept record.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Band {
+    High,
+    Medium,
+    Low,
+    Unknown,
+}
+
+impl Band {
+    pub fn of(confidence: Option<f64>) -> Self {
+        match confidence {
+            Some(c) if c >= 0.75 => Band::High,
+            Some(c) if c >= 0.4 => Band::Medium,
+            Some(_) => Band::Low,
+            None => Band::Unknown,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Band::High => "high",
+            Band::Medium => "medium",
+            Band::Low => "low",
+            Band::Unknown => "unknown",
+        }
+    }
+
+    fn fill(self) -> &'static str {
+        match self {
+            Band::High => "#9be59b",
+            Band::Medium => "#f5e08c",
+            Band::Low => "#f5b7b1",
+            Band::Unknown => "#dddddd",
+        }
+    }
+}
+
+/// The concepts and relations to draw.
+pub struct Subgraph {
+    // Name -> confidence, None for dangling endpoints. Sorted for stable output.
+    pub nodes: BTreeMap<String, Option<f64>>,
+    pub edges: Vec<Relation>,
+}
+
+/// Every concept and relation, or with `around`, the concepts within
+/// `depth` relations of it (either direction) and every relation between
+/// two of them.
+pub fn collect(db: &Database, around: Option<&str>, depth: usize) -> Result<Subgraph> {
+    let confidences = db.concept_confidences()?;
+    let all = db.list_all_relations()?;
+    let edges = match around {
+        None => all,
+        Some(center) => {
+            let mut reached = HashSet::from([center.to_string()]);
+            let mut frontier = reached.clone();
+            for _ in 0..depth {
+                let mut next = HashSet::new();
+                for rel in &all {
+                    for (near, far) in [(&rel.from, &rel.to), (&rel.to, &rel.from)] {
+                        if frontier.contains(near) && reached.insert(far.clone()) {
+                            next.insert(far.clone());
+                        }
+                    }
+                }
+                frontier = next;
+            }
+            all.into_iter().filter(|r| reached.contains(&r.from) && reached.contains(&r.to)).collect()
+        }
+    };
+
+    let mut nodes = BTreeMap::new();
+    match around {
+        None => nodes.extend(confidences.iter().map(|(name, c)| (name.clone(), Some(*c)))),
+        Some(center) => {
+            nodes.insert(center.to_string(), confidences.get(center).copied());
+        }
+    }
+    for rel in &edges {
+        for end in [&rel.from, &rel.to] {
+            nodes.entry(end.clone()).or_insert_with(|| confidences.get(end).copied());
+        }
+    }
+    Ok(Subgraph { nodes, edges })
+}
+
+pub fn render(graph: &Subgraph, format: GraphFormat) -> String {
+    match format {
+        GraphFormat::Dot => dot(graph),
+        GraphFormat::Mermaid => mermaid(graph),
+        GraphFormat::GraphMl => graphml(graph),
+    }
+}
+
+fn label(name: &str, confidence: Option<f64>) -> String {
+    match confidence {
+        Some(c) => format!("{} ({:.2})", name, c),
+        None => name.to_string(),
+    }
+}
+
+fn dot(graph: &Subgraph) -> String {
+    let q = |s: &str| format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""));
+    let mut out = String::from("digraph mother {\n  rankdir=LR;\n  node [shape=box, style=\"rounded,filled\"];\n");
+    for (name, confidence) in &graph.nodes {
+        let band = Band::of(*confidence);
+        let style = if band == Band::Unknown { ", style=\"rounded,filled,dashed\"" } else { "" };
+        out.push_str(&format!(
+            "  {} [label={}, fillcolor=\"{}\", penwidth={:.1}{}];\n",
+            q(name),
+            q(&label(name, *confidence)),
+            band.fill(),
+            1.0 + 2.0 * confidence.unwrap_or(0.0),
+            style,
+        ));
+    }
+    for rel in &graph.edges {
+        out.push_str(&format!("  {} -> {} [label={}];\n", q(&rel.from), q(&rel.to), q(&rel.relation_type)));
+    }
+    out.push_str("}\n");
+    out
+}
+
+fn mermaid(graph: &Subgraph) -> String {
+    // Mermaid ids must be plain words, so nodes are numbered.
+    let ids: BTreeMap<&str, String> =
+        graph.nodes.keys().enumerate().map(|(i, name)| (name.as_str(), format!("n{}", i))).collect();
+    let text = |s: &str| s.replace('"', "#quot;").replace('|', "#124;");
+    let mut out = String::from("flowchart LR\n");
+    for (name, confidence) in &graph.nodes {
+        out.push_str(&format!(
+            "  {}[\"{}\"]:::{}\n",
+            ids[name.as_str()],
+            text(&label(name, *confidence)),
+            Band::of(*confidence).name(),
+        ));
+    }
+    for rel in &graph.edges {
+        out.push_str(&format!(
+            "  {} -->|\"{}\"| {}\n",
+            ids[rel.from.as_str()],
+            text(&rel.relation_type),
+            ids[rel.to.as_str()],
+        ));
+    }
+    for band in [Band::High, Band::Medium, Band::Low, Band::Unknown] {
+        let dash = if band == Band::Unknown { ",stroke-dasharray:4" } else { "" };
+        out.push_str(&format!("  classDef {} fill:{}{}\n", band.name(), band.fill(), dash));
+    }
+    out
+}
+
+fn graphml(graph: &Subgraph) -> String {
+    let x = |s: &str| {
+        s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+    };
+    let mut out = String::from(concat!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+        "<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n",
+        "  <key id=\"label\" for=\"node\" attr.name=\"label\" attr.type=\"string\"/>\n",
+        "  <key id=\"confidence\" for=\"node\" attr.name=\"confidence\" attr.type=\"double\"/>\n",
+        "  <key id=\"band\" for=\"node\" attr.name=\"band\" attr.type=\"string\"/>\n",
+        "  <key id=\"color\" for=\"node\" attr.name=\"color\" attr.type=\"string\"/>\n",
+        "  <key id=\"relation\" for=\"edge\" attr.name=\"relation\" attr.type=\"string\"/>\n",
+        "  <graph id=\"mother\" edgedefault=\"directed\">\n",
+    ));
+    for (name, confidence) in &graph.nodes {
+        let band = Band::of(*confidence);
+        out.push_str(&format!("    <node id=\"{}\">\n", x(name)));
+        out.push_str(&format!("      <data key=\"label\">{}</data>\n", x(name)));
+        if let Some(c) = confidence {
+            out.push_str(&format!("      <data key=\"confidence\">{}</data>\n", c));
+        }
+        out.push_str(&format!("      <data key=\"band\">{}</data>\n", band.name()));
+        out.push_str(&format!("      <data key=\"color\">{}</data>\n", band.fill()));
+        out.push_str("    </node>\n");
+    }
+    for rel in &graph.edges {
+        out.push_str(&format!(
+            "    <edge id=\"e{}\" source=\"{}\" target=\"{}\">\n      <data key=\"relation\">{}</data>\n    </edge>\n",
+            rel.id,
+            x(&rel.from),
+            x(&rel.to),
+            x(&rel.relation_type),
+        ));
+    }
+    out.push_str("  </graph>\n</graphml>\n");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn relation(id: i64, from: &str, relation_type: &str, to: &str) -> Relation {
+        Relation { id, from: from.into(), relation_type: relation_type.into(), to: to.into(), created_at: String::new() }
+    }
+
+    fn names(graph: &Subgraph) -> Vec<&str> {
+        graph.nodes.keys().map(String::as_str).collect()
+    }
+
+    fn edges(graph: &Subgraph) -> Vec<(&str, &str)> {
+        graph.edges.iter().map(|r| (r.from.as_str(), r.to.as_str())).collect()
+    }
+
+    #[test]
+    fn depth_limits_the_nodes_not_the_edges_between_them() {
+        let db = Database::init(":memory:").unwrap();
+        db.upsert_concept("a", "centre", 0.9).unwrap();
+        for (from, to) in [("a", "b"), ("c", "a"), ("b", "c"), ("c", "d"), ("d", "e")] {
+            db.upsert_relation(from, "uses", to).unwrap();
+        }
+
+        let graph = collect(&db, Some("a"), 0).unwrap();
+        assert_eq!((names(&graph), edges(&graph)), (vec!["a"], vec![]));
+        // b -> c joins two nodes of the outer ring.
+        let graph = collect(&db, Some("a"), 1).unwrap();
+        assert_eq!(names(&graph), ["a", "b", "c"]);
+        assert_eq!(edges(&graph), [("a", "b"), ("c", "a"), ("b", "c")]);
+        let graph = collect(&db, Some("a"), 2).unwrap();
+        assert_eq!(names(&graph), ["a", "b", "c", "d"]);
+        assert_eq!(graph.nodes["a"], Some(0.9));
+        assert_eq!(graph.nodes["d"], None);
+        assert_eq!(collect(&db, None, 0).unwrap().edges.len(), 5);
+    }
+
+    #[test]
+    fn hubs_keep_every_relation() {
+        let db = Database::init(":memory:").unwrap();
+        for i in 0..600 {
+            db.upsert_relation("hub", "links to", &format!("n{}", i)).unwrap();
+        }
+        assert_eq!(collect(&db, Some("hub"), 1).unwrap().edges.len(), 600);
+    }
+
+    #[test]
+    fn renderers_escape_names_and_types() {
+        let name = r#"say "hi" <b>&|\"#;
+        let graph = Subgraph {
+            nodes: BTreeMap::from([(name.to_string(), Some(0.5)), ("plain".to_string(), None)]),
+            edges: vec![relation(7, name, r#"a|"b""#, "plain")],
+        };
+
+        let dot = render(&graph, GraphFormat::Dot);
+        assert!(dot.contains(r#"  "say \"hi\" <b>&|\\" -> "plain" [label="a|\"b\""];"#), "{}", dot);
+        assert!(dot.contains(r#"label="say \"hi\" <b>&|\\ (0.50)""#), "{}", dot);
+
+        let mermaid = render(&graph, GraphFormat::Mermaid);
+        assert!(mermaid.contains(r#"  n1["say #quot;hi#quot; <b>&#124;\ (0.50)"]:::medium"#), "{}", mermaid);
+        assert!(mermaid.contains(r#"  n1 -->|"a#124;#quot;b#quot;"| n0"#), "{}", mermaid);
+        assert!(mermaid.contains(r#"  n0["plain"]:::unknown"#), "{}", mermaid);
+
+        let graphml = render(&graph, GraphFormat::GraphMl);
+        assert!(graphml.contains(r#"<node id="say &quot;hi&quot; &lt;b&gt;&amp;|\">"#), "{}", graphml);
+        assert!(graphml.contains(r#"<data key="relation">a|&quot;b&quot;</data>"#), "{}", graphml);
+        assert!(!graphml.contains("<b>"), "{}", graphml);
+    }
+}
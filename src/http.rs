@@ -0,0 +1,257 @@
+// `mother-terminal serve`: the `api` operations as a JSON API on 127.0.0.1.
+// Requests are handled one at a time.
+//
+//   GET  /concepts[?limit=N]           POST /concepts   {name, definition, confidence?}
+//   GET  /concepts/<name>              GET  /proposal
+//   GET  /relations?concept=X          POST /proposal/confirm | /proposal/reject
+//   GET  /episodes[?limit=N]           POST /relations  {from, type, to}
+//   GET  /search?q=X[&limit=N]         POST /episodes   {outcome, summary}
+//   GET  /path?from=A&to=B[&max=N]
+//
+// Browsers let any page POST text/plain across origins without asking, so
+// writes must say `Content-Type: application/json` (else 415) and requests
+// from pages other than localhost ones are refused (403). So is a `Host`
+// other than 127.0.0.1 or localhost on our port, which is what a page that
+// rebinds its own DNS name to 127.0.0.1 would send.
+use std::collections::HashMap;
+
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+use tiny_http::{Header, Method, Request, Response, Server};
+
+use mother_terminal::api::{Api, ApiError, ApiResult, ErrorKind, NewConcept, NewEpisode, NewRelation, DEFAULT_MAX_HOPS};
+use mother_terminal::config::Config;
+
+pub const DEFAULT_PORT: u16 = 7717;
+
+type Reply = (u16, Value);
+
+/// Serves until the process is killed; errors only if the port can't be bound.
+pub fn serve(config: &Config, port: u16) -> Result<(), String> {
+    let addr = format!("127.0.0.1:{}", port);
+    let server = Server::http(&addr).map_err(|e| format!("cannot listen on {}: {}", addr, e))?;
+    let mut api = Api::open(config)?;
+    eprintln!("MOTHER: serving {} on http://{}", config.db_path, addr);
+
+    for mut request in server.incoming_requests() {
+        let (status, body) = handle(&mut api, &mut request, port);
+        let header = Header::from_bytes("Content-Type", "application/json").expect("static header");
+        let response = Response::from_string(body.to_string()).with_status_code(status).with_header(header);
+        if let Err(e) = request.respond(response) {
+            eprintln!("MOTHER: failed to respond: {}", e);
+        }
+    }
+    Ok(())
+}
+
+fn handle(api: &mut Api, request: &mut Request, port: u16) -> Reply {
+    let header = |name: &'static str| {
+        request.headers().iter().find(|h| h.field.equiv(name)).map(|h| h.value.as_str().to_string())
+    };
+    let (host, origin, content_type) = (header("Host"), header("Origin"), header("Content-Type"));
+    if let Err(reply) = guard(request.method(), port, host.as_deref(), origin.as_deref(), content_type.as_deref()) {
+        return reply;
+    }
+    let url = request.url().to_string();
+    let (path, query) = url.split_once('?').unwrap_or((&url, ""));
+    let query = parse_query(query);
+    // '+' is only a space in the query string; `/concepts/c++` means c++.
+    let segments: Vec<String> = path.split('/').filter(|s| !s.is_empty()).map(decode).collect();
+    let segments: Vec<&str> = segments.iter().map(String::as_str).collect();
+    let param = |name: &str| query.get(name).ok_or_else(|| invalid(format!("missing ?{}=", name)));
+
+    let result = match (request.method(), segments.as_slice()) {
+        (Method::Get, ["concepts"]) => api.concepts(number(&query, "limit", 20)),
+        (Method::Get, ["concepts", name]) => api.concept(name),
+        (Method::Post, ["concepts"]) => {
+            return reply(202, body::<NewConcept>(request).and_then(|c| api.learn(&c)));
+        }
+        (Method::Get, ["proposal"]) => Ok(api.pending()),
+        (Method::Post, ["proposal", "confirm"]) => api.decide(true),
+        (Method::Post, ["proposal", "reject"]) => api.decide(false),
+        (Method::Get, ["relations"]) => param("concept").and_then(|c| api.relations(c, number(&query, "limit", 200))),
+        (Method::Post, ["relations"]) => body::<NewRelation>(request).and_then(|r| api.relate(&r)),
+        (Method::Get, ["episodes"]) => api.episodes(number(&query, "limit", 20)),
+        (Method::Post, ["episodes"]) => body::<NewEpisode>(request).and_then(|e| api.record_episode(&e)),
+        (Method::Get, ["search"]) => param("q").and_then(|q| api.search(q, number(&query, "limit", 20))),
+        (Method::Get, ["path"]) => param("from")
+            .and_then(|from| Ok((from, param("to")?)))
+            .and_then(|(from, to)| api.path(from, to, number(&query, "max", DEFAULT_MAX_HOPS))),
+        (_, ["concepts" | "proposal" | "relations" | "episodes" | "search" | "path", ..]) => {
+            return (405, json!({ "error": "method not allowed" }));
+        }
+        _ => Err(ApiError::new(ErrorKind::NotFound, "no such endpoint")),
+    };
+    reply(200, result)
+}
+
+/// Refuses cross-site requests: a `Host` that isn't us, a non-localhost
+/// `Origin`, or a write whose body isn't declared JSON (which browsers won't
+/// send cross-site unasked).
+fn guard(
+    method: &Method,
+    port: u16,
+    host: Option<&str>,
+    origin: Option<&str>,
+    content_type: Option<&str>,
+) -> Result<(), Reply> {
+    let Some(host) = host else {
+        return Err((400, json!({ "error": "missing Host header" })));
+    };
+    if !is_our_host(host, port) {
+        return Err((403, json!({ "error": format!("requests for host {} are not allowed", host) })));
+    }
+    if let Some(origin) = origin
+        && !is_local_origin(origin)
+    {
+        return Err((403, json!({ "error": format!("requests from {} are not allowed", origin) })));
+    }
+    let json_body = content_type
+        .and_then(|t| t.split(';').next())
+        .is_some_and(|t| t.trim().eq_ignore_ascii_case("application/json"));
+    if *method != Method::Get && !json_body {
+        return Err((415, json!({ "error": "send Content-Type: application/json" })));
+    }
+    Ok(())
+}
+
+/// `http(s)://localhost`, `127.0.0.1` or `[::1]`, on any port.
+fn is_local_origin(origin: &str) -> bool {
+    let Some(authority) = origin.strip_prefix("http://").or_else(|| origin.strip_prefix("https://")) else {
+        return false;
+    };
+    let host = match authority.rsplit_once(':') {
+        Some((host, port)) if !port.is_empty() && port.bytes().all(|b| b.is_ascii_digit()) => host,
+        _ => authority,
+    };
+    matches!(host, "localhost" | "127.0.0.1" | "[::1]")
+}
+
+/// `127.0.0.1` or `localhost` on `port`; the port may be left out only
+/// when it is 80.
+fn is_our_host(host: &str, port: u16) -> bool {
+    let (name, given) = match host.rsplit_once(':') {
+        Some((name, given)) => (name, given.parse().ok()),
+        None => (host, Some(80)),
+    };
+    given == Some(port) && (name == "127.0.0.1" || name.eq_ignore_ascii_case("localhost"))
+}
+
+/// `ok` for success, otherwise the status for the error's kind.
+fn reply(ok: u16, result: ApiResult) -> Reply {
+    match result {
+        Ok(v) => (ok, v),
+        Err(e) => {
+            let status = match e.kind {
+                ErrorKind::Invalid => 400,
+                ErrorKind::NotFound => 404,
+                ErrorKind::Conflict => 409,
+                ErrorKind::Internal => 500,
+            };
+            (status, json!(e))
+        }
+    }
+}
+
+fn invalid(message: String) -> ApiError {
+    ApiError::new(ErrorKind::Invalid, message)
+}
+
+fn body<T: DeserializeOwned>(request: &mut Request) -> Result<T, ApiError> {
+    let mut text = String::new();
+    if let Err(e) = request.as_reader().read_to_string(&mut text) {
+        return Err(invalid(format!("cannot read body: {}", e)));
+    }
+    serde_json::from_str(&text).map_err(|e| invalid(format!("bad JSON body: {}", e)))
+}
+
+/// Numeric query parameter `name`, falling back to `default`.
+fn number(query: &HashMap<String, String>, name: &str, default: usize) -> usize {
+    query.get(name).and_then(|v| v.parse().ok()).unwrap_or(default)
+}
+
+fn parse_query(query: &str) -> HashMap<String, String> {
+    query
+        .split('&')
+        .filter_map(|pair| pair.split_once('=').or(Some((pair, ""))))
+        .filter(|(k, _)| !k.is_empty())
+        .map(|(k, v)| (decode(&k.replace('+', " ")), decode(&v.replace('+', " "))))
+        .collect()
+}
+
+/// Percent-decoding; `parse_query` turns `+` into a space first.
+fn decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' => {
+                let hex = bytes.get(i + 1..i + 3).and_then(|h| std::str::from_utf8(h).ok());
+                match hex.and_then(|h| u8::from_str_radix(h, 16).ok()) {
+                    Some(b) => {
+                        out.push(b);
+                        i += 3;
+                        continue;
+                    }
+                    None => out.push(b'%'),
+                }
+            }
+            b => out.push(b),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PORT: u16 = DEFAULT_PORT;
+    const HOST: Option<&str> = Some("127.0.0.1:7717");
+
+    #[test]
+    fn rejects_other_hosts() {
+        for host in ["evil.example:7717", "evil.example", "127.0.0.1:8080", "127.0.0.1", "localhost.evil.example:7717"] {
+            let err = guard(&Method::Get, PORT, Some(host), None, None).unwrap_err();
+            assert_eq!(err.0, 403, "{}", host);
+        }
+        assert_eq!(guard(&Method::Get, PORT, None, None, None).unwrap_err().0, 400);
+        for host in ["127.0.0.1:7717", "localhost:7717", "LocalHost:7717"] {
+            assert!(guard(&Method::Get, PORT, Some(host), None, None).is_ok(), "{}", host);
+        }
+        assert!(is_our_host("localhost", 80));
+    }
+
+    #[test]
+    fn plus_is_a_space_only_in_queries() {
+        assert_eq!(decode("c++"), "c++");
+        assert_eq!(decode("caf%C3%A9%2B"), "café+");
+        let query = parse_query("q=json+web%2Btokens&limit=5");
+        assert_eq!(query["q"], "json web+tokens");
+        assert_eq!(query["limit"], "5");
+    }
+
+    #[test]
+    fn rejects_foreign_origins() {
+        for origin in ["https://evil.example", "null", "http://localhost.evil.example", "http://127.0.0.1.nip.io:7717"] {
+            let err = guard(&Method::Get, PORT, HOST, Some(origin), None).unwrap_err();
+            assert_eq!(err.0, 403, "{}", origin);
+        }
+        for origin in ["http://localhost", "http://localhost:3000", "https://127.0.0.1:7717", "http://[::1]:8080"] {
+            assert!(guard(&Method::Get, PORT, HOST, Some(origin), None).is_ok(), "{}", origin);
+        }
+    }
+
+    #[test]
+    fn rejects_writes_without_json_content_type() {
+        for content_type in [None, Some("text/plain"), Some("application/x-www-form-urlencoded"), Some("multipart/form-data")] {
+            let err = guard(&Method::Post, PORT, HOST, None, content_type).unwrap_err();
+            assert_eq!(err.0, 415, "{:?}", content_type);
+        }
+        assert!(guard(&Method::Post, PORT, HOST, None, Some("application/json")).is_ok());
+        assert!(guard(&Method::Post, PORT, HOST, Some("http://localhost:5173"), Some("Application/JSON; charset=utf-8")).is_ok());
+        assert!(guard(&Method::Get, PORT, HOST, None, None).is_ok());
+    }
+}
@@ -0,0 +1,183 @@
+use std::fmt;
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+use crate::modules::Screen;
+
+/// One key plus the modifiers that must be held (Shift is ignored for chars).
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct KeyChord {
+    pub code: KeyCode,
+    pub mods: KeyModifiers,
+}
+
+pub fn key(code: KeyCode) -> KeyChord {
+    KeyChord { code, mods: KeyModifiers::NONE }
+}
+
+pub fn ch(c: char) -> KeyChord {
+    key(KeyCode::Char(c))
+}
+
+pub fn ctrl(c: char) -> KeyChord {
+    KeyChord { code: KeyCode::Char(c), mods: KeyModifiers::CONTROL }
+}
+
+pub fn alt(code: KeyCode) -> KeyChord {
+    KeyChord { code, mods: KeyModifiers::ALT }
+}
+
+impl KeyChord {
+    pub fn matches(&self, ev: &KeyEvent) -> bool {
+        let mut mods = ev.modifiers;
+        if matches!(ev.code, KeyCode::Char(_)) {
+            mods.remove(KeyModifiers::SHIFT);
+        }
+        self.code == ev.code && self.mods == mods
+    }
+}
+
+impl fmt::Display for KeyChord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.mods.contains(KeyModifiers::CONTROL) {
+            write!(f, "Ctrl+")?;
+        }
+        if self.mods.contains(KeyModifiers::ALT) {
+            write!(f, "Alt+")?;
+        }
+        match self.code {
+            KeyCode::Char(' ') => write!(f, "Space"),
+            KeyCode::Char(c) if self.mods.contains(KeyModifiers::CONTROL) => {
+                write!(f, "{}", c.to_ascii_uppercase())
+            }
+            KeyCode::Char(c) => write!(f, "{}", c),
+            KeyCode::Up => write!(f, "↑"),
+            KeyCode::Down => write!(f, "↓"),
+            KeyCode::Left => write!(f, "←"),
+            KeyCode::Right => write!(f, "→"),
+            KeyCode::F(n) => write!(f, "F{}", n),
+            other => write!(f, "{}", other),
+        }
+    }
+}
+
+/// A set of equivalent chords and what they do.
+#[derive(Clone)]
+pub struct Binding {
+    pub chords: Vec<KeyChord>,
+    pub help: String,
+}
+
+impl Binding {
+    pub fn new(chords: &[KeyChord], help: impl Into<String>) -> Self {
+        Self { chords: chords.to_vec(), help: help.into() }
+    }
+
+    fn keys_label(&self) -> String {
+        self.chords.iter().map(|c| c.to_string()).collect::<Vec<_>>().join(" / ")
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum GlobalAction {
+    Quit,
+    Suspend,
+    Help,
+    Notifications,
+    Undo,
+    Redo,
+    Show(Screen),
+    ToggleSplit,
+    SwitchPane,
+    // Moves the split divider by this many percent.
+    ResizeSplit(i16),
+}
+
+/// Central registry: global bindings handled by the TUI loop, plus the
+/// bindings each screen's module declares for the help overlay.
+pub struct Keymap {
+    global: Vec<(Binding, GlobalAction)>,
+    screens: Vec<(Screen, &'static str, Vec<Binding>)>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Keymap {
+    pub fn new() -> Self {
+        let global = vec![
+            (Binding::new(&[ctrl('q'), ctrl('c')], "quit"), GlobalAction::Quit),
+            (Binding::new(&[ctrl('z')], "suspend to shell (resume with fg)"), GlobalAction::Suspend),
+            (Binding::new(&[key(KeyCode::F(1)), ch('?')], "toggle this help"), GlobalAction::Help),
+            (Binding::new(&[key(KeyCode::F(9)), ctrl('n')], "notification history"), GlobalAction::Notifications),
+            (Binding::new(&[ctrl('u')], "undo last change to the knowledge base"), GlobalAction::Undo),
+            (Binding::new(&[ctrl('y')], "redo"), GlobalAction::Redo),
+            (Binding::new(&[key(KeyCode::F(5)), ctrl('t')], "toggle split workspace"), GlobalAction::ToggleSplit),
+            (Binding::new(&[key(KeyCode::F(6)), ctrl('w')], "switch pane focus"), GlobalAction::SwitchPane),
+            (Binding::new(&[alt(KeyCode::Left)], "move split divider left"), GlobalAction::ResizeSplit(-5)),
+            (Binding::new(&[alt(KeyCode::Right)], "move split divider right"), GlobalAction::ResizeSplit(5)),
+        ];
+        Self { global, screens: Vec::new() }
+    }
+
+    /// Adds the shortcut that switches to a registered screen.
+    pub fn add_screen(&mut self, screen: Screen, title: &str, chords: &[KeyChord]) {
+        debug_assert!(
+            !chords.iter().any(|c| self.global.iter().any(|(g, _)| g.chords.contains(c))),
+            "{} shortcut is already taken",
+            title
+        );
+        self.global.push((Binding::new(chords, format!("{} screen", title)), GlobalAction::Show(screen)));
+    }
+
+    /// Records a module's bindings. Chords already taken by a global binding
+    /// never reach the module, so declaring one is a programming error.
+    pub fn register(&mut self, screen: Screen, title: &'static str, bindings: Vec<Binding>) {
+        for b in &bindings {
+            for c in &b.chords {
+                debug_assert!(
+                    !self.global.iter().any(|(g, _)| g.chords.contains(c)),
+                    "{} binding '{}' shadows a global key",
+                    title,
+                    c
+                );
+            }
+        }
+        self.screens.retain(|(s, _, _)| *s != screen);
+        self.screens.push((screen, title, bindings));
+    }
+
+    /// `text_entry` disables plain-char globals (`?`) while a module is typing.
+    pub fn global_action(&self, ev: &KeyEvent, text_entry: bool) -> Option<GlobalAction> {
+        self.global.iter().find_map(|(b, action)| {
+            let hit = b.chords.iter().any(|c| {
+                c.matches(ev) && !(text_entry && c.mods.is_empty() && matches!(c.code, KeyCode::Char(_)))
+            });
+            hit.then_some(*action)
+        })
+    }
+
+    pub fn help_lines(&self, screen: Screen) -> Vec<String> {
+        let width = self.global
+            .iter()
+            .map(|(b, _)| b)
+            .chain(self.screens.iter().flat_map(|(_, _, bs)| bs))
+            .map(|b| b.keys_label().chars().count())
+            .max()
+            .unwrap_or(0);
+        let line = |b: &Binding| format!("  {:<width$}  {}", b.keys_label(), b.help, width = width);
+
+        let mut out = Vec::new();
+        if let Some((_, title, bindings)) = self.screens.iter().find(|(s, _, _)| *s == screen) {
+            out.push(title.to_string());
+            out.extend(bindings.iter().map(line));
+            out.push(String::new());
+        }
+        out.push("GLOBAL".to_string());
+        out.extend(self.global.iter().map(|(b, _)| line(b)));
+        out
+    }
+}
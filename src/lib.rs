@@ -0,0 +1,35 @@
+//! MOTHER's knowledge base as a library: storage, the command interpreter
+//! and the screens, for the TUI binary and any other front-end.
+//!
+//! - [`db::Database`]: the SQLite store; every query and mutation, with
+//!   undo/redo and the audit trail.
+//! - [`command`]: the command-line grammar and declarative command registry.
+//! - [`modules::dialog::Dialog`]: the DIALOG interpreter; [`Dialog::run_line`]
+//!   and [`Dialog::source`] work without a terminal.
+//! - [`export`]: the concept graph as DOT, Mermaid or GraphML.
+//! - [`rdf`]: concepts and relations as Turtle or JSON-LD, both ways.
+//! - [`api`]: JSON-shaped operations for programmatic front-ends.
+//! - [`modules`]: the screens and their registry, for terminal front-ends.
+//!
+//! [`Dialog::run_line`]: modules::dialog::Dialog::run_line
+//! [`Dialog::source`]: modules::dialog::Dialog::source
+
+pub mod api;
+pub mod bus;
+pub mod command;
+pub mod config;
+pub mod db;
+pub mod export;
+pub mod keymap;
+pub mod modules;
+pub mod notes;
+pub mod rdf;
+pub mod ui;
+
+#[cfg(test)]
+#[path = "../tests/common/mod.rs"]
+mod common;
+
+pub use config::Config;
+pub use db::Database;
+pub use modules::dialog::Dialog;
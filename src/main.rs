@@ -1,23 +1,30 @@
 use std::io;
-use crossterm::{
-    execute,
-    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
-};
 
+// The TUI front-end; everything it drives lives in the library crate.
 mod app;
-mod db;
-mod modules;
-mod ui;
+mod cli;
+mod http;
+#[cfg(unix)]
+mod rpc;
+mod term;
+
+#[cfg(test)]
+#[path = "../tests/common/mod.rs"]
+mod common;
 
 fn main() -> io::Result<()> {
-    enable_raw_mode()?;
-    let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen)?;
+    // Any arguments select a headless command instead of the TUI.
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if !args.is_empty() {
+        std::process::exit(cli::run(&args));
+    }
+
+    term::install_panic_hook();
+    term::enter()?;
 
     let result = app::run();
 
-    disable_raw_mode()?;
-    execute!(stdout, LeaveAlternateScreen)?;
+    term::leave()?;
 
     if let Err(err) = result {
         eprintln!("{:?}", err);
@@ -1,40 +1,443 @@
+use std::{
+    path::Path,
+    time::{Duration, Instant},
+};
+
 use ratatui::{
     widgets::{Block, Borders, Paragraph},
-    layout::{Layout, Direction, Constraint},
+    layout::{Layout, Direction, Constraint, Rect},
     Frame,
 };
-use crossterm::event::KeyEvent;
+use crossterm::event::{KeyCode, KeyEvent, MouseEvent, MouseEventKind};
+
+use super::{Action, Module};
+use crate::bus::Message;
+use crate::config::{Config, CONFIG_PATH};
+use crate::db::{Activity, Database, OutcomeCounts, Stats};
+use crate::keymap::{key, Binding};
+use crate::ui::{self, notify::{Level, Notifier}};
+
+const RATIO_DAYS: i64 = 7;
+const SCROLLBACK: usize = 500;
 
-use super::Module;
+const HELP: &[&str] = &[
+    "  stats            counts, schema version and file size",
+    "  vacuum           rebuild the database file, reclaiming free pages",
+    "  integrity        run SQLite's integrity check",
+    "  backup <file>    write a consistent copy of the database",
+    "  open <file>      switch every screen to an existing database",
+    "  open --create <file>  the same, starting a new one if it is missing",
+    "  config reload    re-read mother.json for every screen",
+    "  goto <screen>    switch to console, dialog, graph or timeline",
+    "  quit             leave MOTHER",
+    "  clear            clear this output",
+    "  help             this list",
+    "  [PgUp/PgDn] scroll output",
+];
 
-pub struct Console;
+/// Everything the dashboard shows, loaded in one pass.
+struct Snapshot {
+    stats: Stats,
+    schema_version: i64,
+    outcomes: OutcomeCounts,
+    activity: Vec<Activity>,
+    file_size: Option<u64>,
+}
+
+pub struct Console {
+    db: Database,
+    config: Config,
+    notifier: Notifier,
+    snapshot: Result<Snapshot, String>,
+    refreshed_at: Option<Instant>,
+    input: String,
+    output: Vec<String>,
+    // Lines scrolled back from the bottom of `output`.
+    scroll: usize,
+    // Path the other screens should reopen, sent as `Action::Reopen`.
+    reopen: Option<String>,
+    output_area: Rect,
+}
 
 impl Console {
-    pub fn new() -> Self {
-        Self
+    pub fn new(db: Database, config: Config, notifier: Notifier) -> Self {
+        Self {
+            db,
+            config,
+            notifier,
+            snapshot: Err("not loaded".into()),
+            refreshed_at: None,
+            input: String::new(),
+            output: vec!["SYSTEM: OPERATOR SHELL READY. Type 'help'.".into()],
+            scroll: 0,
+            reopen: None,
+            output_area: Rect::default(),
+        }
+    }
+
+    fn push(&mut self, line: impl Into<String>) {
+        self.output.push(line.into());
+        if self.output.len() > SCROLLBACK {
+            self.output.drain(0..SCROLLBACK / 4);
+        }
+        self.scroll = 0;
+    }
+
+    fn refresh(&mut self) {
+        self.snapshot = self.load().map_err(|e| e.to_string());
+        self.refreshed_at = Some(Instant::now());
+    }
+
+    fn load(&self) -> rusqlite::Result<Snapshot> {
+        Ok(Snapshot {
+            stats: self.db.stats()?,
+            schema_version: self.db.schema_version()?,
+            outcomes: self.db.outcome_counts(RATIO_DAYS)?,
+            activity: self.db.recent_activity(8)?,
+            file_size: std::fs::metadata(self.db.path()).ok().map(|m| m.len()),
+        })
+    }
+
+    fn handle_command(&mut self, line: &str) -> Vec<Action> {
+        let mut words = line.split_whitespace();
+        let Some(cmd) = words.next() else { return Vec::new() };
+        let arg = words.collect::<Vec<_>>().join(" ");
+
+        match (cmd.to_lowercase().as_str(), arg.as_str()) {
+            ("help", _) => {
+                self.push("SYSTEM: Commands:");
+                for h in HELP {
+                    self.push(*h);
+                }
+            }
+            ("clear", _) => self.output.clear(),
+            ("stats", _) => self.cmd_stats(),
+            ("vacuum", _) => {
+                let before = file_size(self.db.path());
+                match self.db.vacuum() {
+                    Ok(()) => {
+                        let after = file_size(self.db.path());
+                        self.push(format!("SYSTEM: VACUUM done. {} -> {}", before, after));
+                        self.notifier.info(format!("VACUUM done ({} -> {}).", before, after));
+                        self.refresh();
+                    }
+                    Err(e) => self.notifier.error(format!("VACUUM failed: {}", e)),
+                }
+            }
+            ("integrity", _) => match self.db.integrity_check() {
+                Ok(rows) if rows == ["ok"] => self.push("SYSTEM: INTEGRITY OK."),
+                Ok(rows) => {
+                    self.notifier.warn(format!("Integrity check found {} problem(s).", rows.len()));
+                    self.push(format!("SYSTEM: INTEGRITY PROBLEMS ({}):", rows.len()));
+                    for r in rows {
+                        self.push(format!("  {}", r));
+                    }
+                }
+                Err(e) => self.notifier.error(format!("Integrity check failed: {}", e)),
+            },
+            ("backup", "") => self.push("SYSTEM: Format is: backup <file>"),
+            ("backup", dest) => {
+                if Path::new(dest).exists() {
+                    self.push(format!("SYSTEM: '{}' already exists; refusing to overwrite.", dest));
+                    return Vec::new();
+                }
+                match self.db.backup_to(dest) {
+                    Ok(()) => {
+                        let msg = format!("Backup written to {} ({}).", dest, file_size(dest));
+                        self.push(format!("SYSTEM: {}", msg));
+                        self.notifier.info(msg);
+                    }
+                    Err(e) => self.notifier.error(format!("Backup failed: {}", e)),
+                }
+            }
+            ("open", arg) => {
+                let (create, path) = match arg.strip_prefix("--create") {
+                    Some(rest) if rest.is_empty() || rest.starts_with(' ') => (true, rest.trim()),
+                    _ => (false, arg),
+                };
+                if path.is_empty() {
+                    self.push("SYSTEM: Format is: open [--create] <file>");
+                } else {
+                    self.open(path.to_string(), create);
+                }
+            }
+            ("config", "reload") => return self.reload_config(),
+            ("goto", "") => self.push("SYSTEM: Format is: goto <console|dialog|graph|timeline>"),
+            ("goto", name) => return vec![Action::Navigate(name.to_lowercase())],
+            ("quit" | "exit", _) => return vec![Action::Quit],
+            _ => self.push(format!("SYSTEM: Unknown command '{}'. Type 'help'.", line.trim())),
+        }
+        Vec::new()
+    }
+
+    /// Warnings that are new since the previous snapshot, for a one-off toast.
+    fn new_warnings(before: &Stats, after: &Stats) -> Vec<Action> {
+        let mut out = Vec::new();
+        if after.dangling_relations > before.dangling_relations {
+            out.push(Action::Notify(
+                Level::Warning,
+                format!("{} relation(s) now point at missing concepts.", after.dangling_relations),
+            ));
+        }
+        out
+    }
+
+    fn cmd_stats(&mut self) {
+        self.refresh();
+        let lines = match &self.snapshot {
+            Ok(s) => vec![
+                format!(
+                    "SYSTEM: concepts {}  relations {}  episodes {}  dangling {}",
+                    s.stats.concepts, s.stats.relations, s.stats.episodes, s.stats.dangling_relations
+                ),
+                format!(
+                    "  {}  {}  schema v{}",
+                    self.db.path(),
+                    s.file_size.map(human_size).unwrap_or_else(|| "size unknown".into()),
+                    s.schema_version
+                ),
+            ],
+            Err(e) => vec![format!("SYSTEM: stats failed: {}", e)],
+        };
+        for l in lines {
+            self.push(l);
+        }
+    }
+
+    /// Switches to `path`; a missing file is an error unless `create` is set,
+    /// so a typo doesn't quietly start an empty database.
+    fn open(&mut self, path: String, create: bool) {
+        if !create && !Path::new(&path).exists() {
+            self.push(format!("SYSTEM: '{}' does not exist. Use 'open --create {}' to start a new database.", path, path));
+            self.notifier.error(format!("Cannot open {}: no such file.", path));
+            return;
+        }
+        match Database::init(&path) {
+            Ok(db) => {
+                self.db = db;
+                self.config.db_path = path.clone();
+                self.push(format!("SYSTEM: Opened {}.", path));
+                self.notifier.info(format!("Switched database to {}.", path));
+                self.reopen = Some(path);
+                self.refresh();
+            }
+            Err(e) => self.notifier.error(format!("Cannot open {}: {}", path, e)),
+        }
+    }
+
+    /// Re-reads mother.json for every screen; `open` then switches databases
+    /// if its path changed, but only to a file that exists.
+    fn reload_config(&mut self) -> Vec<Action> {
+        match Config::load(CONFIG_PATH) {
+            Ok(cfg) => {
+                let path = cfg.db_path.clone();
+                self.config = Config { db_path: self.config.db_path.clone(), ..cfg.clone() };
+                self.push(format!("SYSTEM: Reloaded {}.", CONFIG_PATH));
+                if path != self.db.path() {
+                    self.open(path.clone(), false);
+                    if self.db.path() != path {
+                        self.push(format!("SYSTEM: Still using {}.", self.db.path()));
+                    }
+                }
+                vec![Action::ReloadConfig(cfg)]
+            }
+            Err(e) => {
+                self.notifier.error(format!("Config reload failed: {}", e));
+                Vec::new()
+            }
+        }
+    }
+
+    fn dashboard(&self) -> String {
+        let path = self.db.path();
+        let snap = match &self.snapshot {
+            Ok(s) => s,
+            Err(e) => {
+                return format!(
+                    "STATUS: DEGRADED\nDATABASE: ERROR ({})\nPATH: {}\n\nTry 'integrity' or 'open <file>'.",
+                    e, path
+                );
+            }
+        };
+
+        let mut out = String::new();
+        out.push_str("STATUS: ONLINE\n");
+        out.push_str(&format!(
+            "DATABASE: CONNECTED  {}  ({})  schema v{}\n\n",
+            path,
+            snap.file_size.map(human_size).unwrap_or_else(|| "size unknown".into()),
+            snap.schema_version
+        ));
+
+        let s = &snap.stats;
+        out.push_str(&format!(
+            "CONCEPTS: {}   RELATIONS: {}   EPISODES: {}\n",
+            s.concepts, s.relations, s.episodes
+        ));
+
+        let o = &snap.outcomes;
+        let total = o.ok + o.fail + o.note;
+        let pct = |n: i64| if total == 0 { 0 } else { n * 100 / total };
+        out.push_str(&format!(
+            "LAST {} DAYS: ok {} ({}%)  fail {} ({}%)  note {} ({}%)\n\n",
+            RATIO_DAYS, o.ok, pct(o.ok), o.fail, pct(o.fail), o.note, pct(o.note)
+        ));
+
+        out.push_str("WARNINGS:\n");
+        let mut warned = false;
+        if s.dangling_relations > 0 {
+            out.push_str(&format!(
+                "  ! {} relation(s) point at concepts that do not exist\n",
+                s.dangling_relations
+            ));
+            warned = true;
+        }
+        if total > 0 && o.fail > o.ok {
+            out.push_str("  ! more failures than successes this week\n");
+            warned = true;
+        }
+        if !warned {
+            out.push_str("  none\n");
+        }
+
+        out.push_str("\nRECENT ACTIVITY:\n");
+        if snap.activity.is_empty() {
+            out.push_str("  (nothing recorded yet)\n");
+        }
+        for a in &snap.activity {
+            out.push_str(&format!("  {}  {:<8} {}\n", short_time(&a.at), a.kind, a.text));
+        }
+        out
     }
 }
 
 impl Module for Console {
-    fn render(&mut self, f: &mut Frame) {
+    fn bindings(&self) -> Vec<Binding> {
+        vec![
+            Binding::new(&[key(KeyCode::Enter)], "run the typed command ('help' lists them)"),
+            Binding::new(&[key(KeyCode::Backspace)], "delete last character"),
+            Binding::new(&[key(KeyCode::PageUp), key(KeyCode::PageDown)], "scroll output"),
+        ]
+    }
+
+    fn captures_text(&self) -> bool {
+        !self.input.is_empty()
+    }
+
+    fn on_enter(&mut self) {
+        self.refresh();
+    }
+
+    /// Echoes what other screens do; data changes also refresh the dashboard.
+    fn on_message(&mut self, from: &str, msg: &Message) {
+        self.push(format!("EVENT {}: {}", from.to_uppercase(), msg));
+        if !matches!(msg, Message::FocusConcept(_) | Message::Notify(..)) {
+            self.refreshed_at = None;
+        }
+    }
+
+    fn tick(&mut self) -> Vec<Action> {
+        let every = Duration::from_secs(self.config.refresh_secs.max(1));
+        if self.refreshed_at.is_some_and(|t| t.elapsed() < every) {
+            return Vec::new();
+        }
+        let before = self.snapshot.as_ref().ok().map(|s| s.stats.clone());
+        self.refresh();
+        match (before, &self.snapshot) {
+            (Some(before), Ok(after)) => Self::new_warnings(&before, &after.stats),
+            _ => Vec::new(),
+        }
+    }
+
+    fn render(&mut self, f: &mut Frame, area: Rect) {
         let layout = Layout::default()
             .direction(Direction::Vertical)
             .constraints([
                 Constraint::Length(3),
-                Constraint::Min(1),
+                Constraint::Percentage(55),
+                Constraint::Min(3),
+                Constraint::Length(3),
             ])
-            .split(f.area());
+            .split(area);
 
-        let header = Paragraph::new("MOTHER SYSTEM CONSOLE  |  [F2] DIALOG  [F3] GRAPH  [Ctrl+Q] QUIT")
+        let header = Paragraph::new("MOTHER SYSTEM CONSOLE  |  [F1] HELP  [F2] DIALOG  [F3] GRAPH  [F4] CONSOLE  [F7] TIMELINE  [Ctrl+Q] QUIT")
             .block(Block::default().borders(Borders::ALL));
 
-        let body = Paragraph::new(
-            "STATUS: ONLINE\nDATABASE: CONNECTED\nMODE: OPERATOR CONTROLLED\n\nAwaiting command..."
-        ).block(Block::default().borders(Borders::ALL));
+        let body = Paragraph::new(self.dashboard())
+            .block(Block::default().borders(Borders::ALL).title("DASHBOARD"));
+
+        // Bottom-anchored scrollback, offset by `scroll`.
+        let visible = layout[2].height.saturating_sub(2) as usize;
+        let max_scroll = self.output.len().saturating_sub(visible);
+        self.scroll = self.scroll.min(max_scroll);
+        let top = max_scroll - self.scroll;
+        let title = if self.scroll > 0 { format!("OUTPUT (-{})", self.scroll) } else { "OUTPUT".to_string() };
+        let output = Paragraph::new(self.output.join("\n"))
+            .scroll((top as u16, 0))
+            .block(Block::default().borders(Borders::ALL).title(title));
+
+        let input = Paragraph::new(format!("> {}", self.input))
+            .block(Block::default().borders(Borders::ALL).title("COMMAND"));
 
         f.render_widget(header, layout[0]);
         f.render_widget(body, layout[1]);
+        f.render_widget(output, layout[2]);
+        self.output_area = layout[2];
+        f.render_widget(input, layout[3]);
+    }
+
+    fn handle_input(&mut self, key: KeyEvent) -> Vec<Action> {
+        match key.code {
+            KeyCode::Char(c) => self.input.push(c),
+            KeyCode::Backspace => { self.input.pop(); }
+            KeyCode::PageUp => self.scroll += 5,
+            KeyCode::PageDown => self.scroll = self.scroll.saturating_sub(5),
+            KeyCode::Enter => {
+                let line = std::mem::take(&mut self.input);
+                if line.trim().is_empty() {
+                    return Vec::new();
+                }
+                self.push(format!("> {}", line));
+                let mut actions = self.handle_command(&line);
+                // `open` and `config reload` may have switched databases.
+                actions.extend(self.reopen.take().map(Action::Reopen));
+                return actions;
+            }
+            _ => {}
+        }
+        Vec::new()
+    }
+
+    fn handle_mouse(&mut self, ev: MouseEvent) -> Vec<Action> {
+        if ui::contains(self.output_area, ev.column, ev.row) {
+            match ev.kind {
+                MouseEventKind::ScrollUp => self.scroll += 3,
+                MouseEventKind::ScrollDown => self.scroll = self.scroll.saturating_sub(3),
+                _ => {}
+            }
+        }
+        Vec::new()
     }
+}
+
+fn file_size(path: &str) -> String {
+    std::fs::metadata(path)
+        .map(|m| human_size(m.len()))
+        .unwrap_or_else(|_| "?".into())
+}
+
+fn human_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit + 1 < UNITS.len() {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 { format!("{} B", bytes) } else { format!("{:.1} {}", size, UNITS[unit]) }
+}
 
-    fn handle_input(&mut self, _key: KeyEvent) {}
+/// "2024-05-01 12:34:56.789 +00:00:00" -> "2024-05-01 12:34".
+fn short_time(ts: &str) -> &str {
+    ts.get(..16).unwrap_or(ts)
 }
@@ -1,181 +1,984 @@
+use std::{
+    collections::{BTreeMap, VecDeque},
+    fs,
+};
+
 use ratatui::{
     widgets::{Block, Borders, Paragraph},
-    layout::{Layout, Direction, Constraint},
+    layout::{Layout, Direction, Constraint, Rect},
     Frame,
 };
-use crossterm::event::{KeyCode, KeyEvent};
+use crossterm::event::{KeyCode, KeyEvent, MouseEvent, MouseEventKind};
 
-use super::Module;
+use super::{Action, Module};
+use crate::command::{self, Command, CommandSpec, ParseError, Registry, Token};
+use crate::bus::{Bus, Message};
 use crate::db::{Database, Concept};
+use crate::export::{self, GraphFormat};
+use crate::notes;
+use crate::rdf::{self, RdfFormat};
+use crate::config::Config;
+use crate::keymap::{ch, key, Binding};
+use crate::ui::{self, notify::Notifier};
+
+const COMMANDS: &[CommandSpec<Dialog, Vec<Action>>] = &[
+    CommandSpec {
+        name: "learn",
+        aliases: &["define"],
+        args: "[--confidence=X] <concept> is <definition>",
+        flags: &["confidence"],
+        free_text: true,
+        help: "propose a concept; confirm with [y], reject with [n]",
+        examples: &["learn jwt is a signed token", "learn --confidence=0.8 \"json web token\" is a compact token format"],
+        run: Dialog::cmd_learn,
+    },
+    CommandSpec {
+        name: "rel",
+        aliases: &["link"],
+        args: "<from> <type> <to>",
+        flags: &[],
+        free_text: false,
+        help: "link two concepts; quote multi-word names (the last words after the type are the target)",
+        examples: &["rel jwt used_for authentication", "rel \"json web token\" uses jws", "rel jwt part_of json web tokens"],
+        run: Dialog::cmd_rel,
+    },
+    CommandSpec {
+        name: "ep",
+        aliases: &["episode"],
+        args: "<ok|fail|note> <summary>",
+        flags: &[],
+        free_text: true,
+        help: "record what worked, what failed, or a note",
+        examples: &["ep ok rotating keys fixed the outage", "ep fail forgot to check expiry"],
+        run: Dialog::cmd_ep,
+    },
+    CommandSpec {
+        name: "episodes",
+        aliases: &["eps"],
+        args: "[count]",
+        flags: &[],
+        free_text: false,
+        help: "recent episodes (default 20)",
+        examples: &["episodes", "episodes 50"],
+        run: Dialog::cmd_episodes,
+    },
+    CommandSpec {
+        name: "show",
+        aliases: &[],
+        args: "<concept>",
+        flags: &[],
+        free_text: false,
+        help: "print a concept record",
+        examples: &["show jwt"],
+        run: Dialog::cmd_show,
+    },
+    CommandSpec {
+        name: "open",
+        aliases: &[],
+        args: "<concept>",
+        flags: &[],
+        free_text: false,
+        help: "view a concept in GRAPH",
+        examples: &["open jwt"],
+        run: Dialog::cmd_open,
+    },
+    CommandSpec {
+        name: "list",
+        aliases: &["ls"],
+        args: "[count]",
+        flags: &[],
+        free_text: false,
+        help: "recent concepts (default 20)",
+        examples: &["list", "list 100"],
+        run: Dialog::cmd_list,
+    },
+    CommandSpec {
+        name: "undo",
+        aliases: &[],
+        args: "",
+        flags: &[],
+        free_text: false,
+        help: "revert the last change to concepts, relations or episodes (also Ctrl+U)",
+        examples: &["undo"],
+        run: |d, cmd| d.cmd_step(cmd, false),
+    },
+    CommandSpec {
+        name: "redo",
+        aliases: &[],
+        args: "",
+        flags: &[],
+        free_text: false,
+        help: "re-apply the last undone change (also Ctrl+Y)",
+        examples: &["redo"],
+        run: |d, cmd| d.cmd_step(cmd, true),
+    },
+    CommandSpec {
+        name: "audit",
+        aliases: &[],
+        args: "[--concept=NAME] [--operator=NAME] [count]",
+        flags: &["concept", "operator"],
+        free_text: false,
+        help: "who changed what, newest first (default 20)",
+        examples: &["audit", "audit --concept=jwt", "audit --operator=ripley 100"],
+        run: Dialog::cmd_audit,
+    },
+    CommandSpec {
+        name: "export",
+        aliases: &[],
+        args: "[--format=F] [--around=CONCEPT] [--depth=N] [--base=IRI] graph|rdf <file>",
+        flags: &["format", "around", "depth", "base"],
+        free_text: false,
+        help: "write the concept graph, or a concept's neighborhood, as a diagram (dot, mermaid, graphml) or as RDF (turtle, jsonld)",
+        examples: &["export graph kb.dot", "export --around=jwt --depth=2 graph jwt.mmd", "export rdf kb.ttl", "export --base=https://kb.example/ rdf kb.jsonld"],
+        run: Dialog::cmd_export,
+    },
+    CommandSpec {
+        name: "import",
+        aliases: &[],
+        args: "[--format=turtle|jsonld] [--base=IRI] notes <dir> | rdf <file>",
+        flags: &["format", "base"],
+        free_text: false,
+        help: "create concepts and relations from Markdown notes with [[links]] or from RDF; changed definitions become proposals",
+        examples: &["import notes ~/notes", "import notes \"design docs\"", "import rdf kb.ttl"],
+        run: Dialog::cmd_import,
+    },
+    CommandSpec {
+        name: "source",
+        aliases: &[],
+        args: "[--yes] [--keep-going] <file>",
+        flags: &["yes", "keep-going"],
+        free_text: false,
+        help: "run each line of a file; --yes confirms proposals (default rejects), --keep-going continues past errors",
+        examples: &["source seed.txt", "source --yes --keep-going \"my notes.txt\""],
+        run: Dialog::cmd_source,
+    },
+    CommandSpec {
+        name: "alias",
+        aliases: &[],
+        args: "[<name> <command...>]",
+        flags: &[],
+        free_text: false,
+        help: "list aliases, or define one for this session (persist them in mother.json)",
+        examples: &["alias", "alias ll list 100"],
+        run: Dialog::cmd_alias,
+    },
+    CommandSpec {
+        name: "unalias",
+        aliases: &[],
+        args: "<name>",
+        flags: &[],
+        free_text: false,
+        help: "remove an alias for this session",
+        examples: &["unalias ll"],
+        run: Dialog::cmd_unalias,
+    },
+    CommandSpec {
+        name: "help",
+        aliases: &[],
+        args: "[command]",
+        flags: &[],
+        free_text: false,
+        help: "list commands, or show one in detail",
+        examples: &["help", "help rel"],
+        run: Dialog::cmd_help,
+    },
+];
 
 #[derive(Clone, Debug)]
 struct Proposal {
     name: String,
     definition: String,
     confidence: f64,
+    // Definition it would overwrite, when an import proposes an update.
+    replaces: Option<String>,
+    // Note path and hash from `import notes`, recorded once this is stored
+    // so a rejected or abandoned update is offered again.
+    note: Option<(String, String)>,
+}
+
+/// How `source` treats proposals and failing lines.
+#[derive(Clone, Copy, Default)]
+pub struct BatchOptions {
+    // Confirm every `learn` proposal; otherwise they are rejected.
+    pub confirm: bool,
+    pub keep_going: bool,
+}
+
+/// What a `source` run did, for the closing report.
+#[derive(Default)]
+pub struct BatchSummary {
+    pub commands: usize,
+    pub created: Vec<String>,
+    pub updated: Vec<String>,
+    pub rejected: Vec<String>,
+    pub relations_added: usize,
+    pub relations_existing: usize,
+    pub episodes: usize,
+    // Line numbers of commands that failed.
+    pub failed: Vec<usize>,
+    // Line that ended the run early, without --keep-going.
+    pub stopped_at: Option<usize>,
+}
+
+impl BatchSummary {
+    pub fn lines(&self, path: &str) -> Vec<String> {
+        let names = |v: &[String]| if v.is_empty() { String::new() } else { format!(" ({})", v.join(", ")) };
+        let mut out = vec![
+            format!("MOTHER: SOURCE {} - {} command(s) run", path, self.commands),
+            format!("  concepts created: {}{}", self.created.len(), names(&self.created)),
+            format!("  concepts updated: {}{}", self.updated.len(), names(&self.updated)),
+            format!("  proposals rejected: {}{}", self.rejected.len(), names(&self.rejected)),
+            format!("  relations added: {}  already present: {}", self.relations_added, self.relations_existing),
+            format!("  episodes recorded: {}", self.episodes),
+        ];
+        if !self.failed.is_empty() {
+            let lines: Vec<String> = self.failed.iter().map(|n| n.to_string()).collect();
+            out.push(format!("  failed: {} (line(s) {})", self.failed.len(), lines.join(", ")));
+        }
+        if let Some(n) = self.stopped_at {
+            out.push(format!("  stopped at line {}; --keep-going runs past errors", n));
+        }
+        if !self.rejected.is_empty() {
+            out.push("  proposals are rejected unless --yes is given".into());
+        }
+        out
+    }
 }
 
 pub struct Dialog {
     input: String,
     history: Vec<String>,
     db: Database,
+    notifier: Notifier,
+    bus: Bus,
+    commands: Registry<Dialog, Vec<Action>>,
     pending: Option<Proposal>,
+    // Proposals waiting behind `pending`, e.g. from `import notes`.
+    queued: VecDeque<Proposal>,
+    // Lines scrolled back from the bottom of `history`.
+    scroll: usize,
+    history_area: Rect,
+    // Set by `db_error` while a command runs.
+    db_failed: bool,
+    // Counts of a running `source`; also blocks nesting.
+    tally: Option<BatchSummary>,
+    // While set, `push` collects output here instead of in `history`.
+    capture: Option<Vec<String>>,
+    // Default for `--base` of `export rdf` and `import rdf`.
+    rdf_base: String,
+    // Aliases from mother.json, replaced by `config reload`; ones defined
+    // with `alias` are kept.
+    config_aliases: BTreeMap<String, String>,
 }
 
 impl Dialog {
-    pub fn new(db: Database) -> Self {
+    /// Aliases and the RDF base IRI come from mother.json.
+    pub fn new(db: Database, notifier: Notifier, bus: Bus, config: &Config) -> Self {
+        let mut commands = Registry::new(COMMANDS);
+        add_aliases(&mut commands, &config.aliases, &notifier);
+        let mut history = vec!["MOTHER: DIALOG READY.".to_string(), "MOTHER: Commands:".to_string()];
+        history.extend(commands.summary());
+        history.push("MOTHER: [Tab] completes a command. If a proposal appears: press [y] to confirm, [n] to reject.".into());
         Self {
             input: String::new(),
-            history: vec![
-                "MOTHER: DIALOG READY.".into(),
-                "MOTHER: Commands:".into(),
-                "  learn <concept> is <definition>".into(),
-                "  rel <from> <type> <to>".into(),
-                "  ep ok <what worked>".into(),
-                "  ep fail <what failed>".into(),
-                "  ep note <note>".into(),
-                "  episodes".into(),
-                "  show <concept>".into(),
-                "  list".into(),
-                "MOTHER: If a proposal appears: press [y] to confirm, [n] to reject.".into(),
-            ],
+            history,
             db,
+            notifier,
+            bus,
+            commands,
             pending: None,
+            queued: VecDeque::new(),
+            scroll: 0,
+            history_area: Rect::default(),
+            db_failed: false,
+            tally: None,
+            capture: None,
+            rdf_base: config.rdf_base.clone(),
+            config_aliases: config.aliases.clone(),
+        }
+    }
+
+    pub fn history(&self) -> &[String] {
+        &self.history
+    }
+
+    /// Runs one line without a terminal and returns the transcript it
+    /// produced: `Err` if it failed. Navigation requests are dropped, and a
+    /// `learn` proposal waits for `decide`.
+    pub fn run_line(&mut self, line: &str) -> Result<Vec<String>, Vec<String>> {
+        let (ok, output) = self.captured(|d| d.execute(line).is_ok());
+        if ok { Ok(output) } else { Err(output) }
+    }
+
+    /// Confirms or rejects the pending proposal and returns the transcript,
+    /// like `run_line`.
+    pub fn decide(&mut self, confirm: bool) -> Vec<String> {
+        self.captured(|d| if confirm { d.confirm_pending() } else { d.reject_pending() }).1
+    }
+
+    /// Name of the concept awaiting confirmation, if any.
+    pub fn pending(&self) -> Option<&str> {
+        self.pending.as_ref().map(|p| p.name.as_str())
+    }
+
+    /// Runs every line of `path` through the command engine. Blank lines and
+    /// `#` comments are skipped; successful lines print nothing, failures
+    /// print their diagnostics, and the summary is printed at the end.
+    /// Returns the summary and that transcript.
+    pub fn source(&mut self, path: &str, opts: BatchOptions) -> Result<(BatchSummary, Vec<String>), String> {
+        if self.tally.is_some() {
+            return Err("source cannot be nested".into());
+        }
+        if self.pending.is_some() {
+            return Err("confirm or reject the pending proposal first".into());
         }
+        let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+        Ok(self.captured(|d| d.run_batch(path, &text, opts)))
+    }
+
+    fn run_batch(&mut self, path: &str, text: &str, opts: BatchOptions) -> BatchSummary {
+        self.tally = Some(BatchSummary::default());
+        for (i, raw) in text.lines().enumerate() {
+            let line = raw.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (ok, output) = self.captured(|d| {
+                let ok = d.execute(line).is_ok();
+                while ok && d.pending.is_some() {
+                    if opts.confirm {
+                        d.confirm_pending();
+                    } else {
+                        d.reject_pending();
+                    }
+                }
+                // A proposal that failed to store fails its line.
+                ok && !d.db_failed
+            });
+            let tally = self.tally.as_mut().expect("set above");
+            if ok {
+                tally.commands += 1;
+                continue;
+            }
+            tally.failed.push(i + 1);
+            self.push(format!("MOTHER: {}:{}: {}", path, i + 1, line));
+            for l in output {
+                self.push(l);
+            }
+            if !opts.keep_going {
+                self.tally.as_mut().expect("set above").stopped_at = Some(i + 1);
+                break;
+            }
+        }
+
+        let summary = self.tally.take().unwrap_or_default();
+        for l in summary.lines(path) {
+            self.push(l);
+        }
+        summary
+    }
+
+    /// Runs `f` with its output collected rather than shown.
+    fn captured<T>(&mut self, f: impl FnOnce(&mut Self) -> T) -> (T, Vec<String>) {
+        let outer = self.capture.replace(Vec::new());
+        let result = f(self);
+        let output = std::mem::replace(&mut self.capture, outer).unwrap_or_default();
+        (result, output)
     }
 
     fn push(&mut self, line: impl Into<String>) {
+        if let Some(out) = self.capture.as_mut() {
+            out.push(line.into());
+            return;
+        }
         self.history.push(line.into());
         if self.history.len() > 240 {
             self.history.drain(0..70);
         }
+        self.scroll = 0;
     }
 
     fn eliza_reflect(&self, text: &str) -> String {
         format!("MOTHER: Why do you say '{}'?",&text)
     }
 
-    fn handle_command(&mut self, line: &str) {
-        let trimmed = line.trim();
-        if trimmed.is_empty() {
-            return;
-        }
+    fn handle_command(&mut self, line: &str) -> Vec<Action> {
+        self.execute(line).unwrap_or_default()
+    }
 
-        // episodes
-        if trimmed.eq_ignore_ascii_case("episodes") {
-            match self.db.list_episodes(20) {
-                Ok(items) if items.is_empty() => self.push("MOTHER: No episodes stored yet."),
-                Ok(items) => {
-                    self.push("MOTHER: Recent episodes:");
-                    for e in items {
-                        self.push(format!("  - [{}] {}  {}", e.outcome, e.captured_at, e.summary));
+    /// Runs one line, printing its output. Fails if the line is not a
+    /// command, is malformed, or hit a database error.
+    fn execute(&mut self, line: &str) -> Result<Vec<Action>, ()> {
+        let line = self.commands.expand(line);
+        self.db_failed = false;
+        self.db.set_command(line.trim());
+        match command::parse(&line) {
+            Ok(None) => Ok(Vec::new()),
+            Ok(Some(cmd)) => match self.commands.find(&cmd.name) {
+                Some(spec) => match (if spec.free_text { Ok(()) } else { cmd.complete() })
+                    .and_then(|()| cmd.check_flags(spec.flags))
+                    .and_then(|()| (spec.run)(self, &cmd))
+                {
+                    Ok(_) if self.db_failed => Err(()),
+                    Ok(actions) => Ok(actions),
+                    Err(e) => {
+                        self.syntax_error(&line, &e);
+                        self.push(format!("  usage: {}   (help {})", spec.usage(), spec.name));
+                        Err(())
                     }
+                },
+                // Anything else is conversation, except in a script.
+                None => {
+                    self.not_a_command(&line);
+                    Err(())
                 }
-                Err(e) => self.push(format!("MOTHER: DB error: {}", e)),
+            },
+            Err(e) if self.is_command(&line) => {
+                self.syntax_error(&line, &e);
+                Err(())
+            }
+            Err(_) => {
+                self.not_a_command(&line);
+                Err(())
             }
+        }
+    }
+
+    fn not_a_command(&mut self, line: &str) {
+        if self.tally.is_some() {
+            self.push("MOTHER: Not a command. Type 'help' for the list.");
+        } else {
+            self.push(self.eliza_reflect(line.trim()));
+        }
+    }
+
+    fn db_error(&mut self, e: rusqlite::Error) {
+        self.db_failed = true;
+        self.push(format!("MOTHER: DB error: {}", e));
+        self.notifier.error(format!("DIALOG: DB error: {}", e));
+    }
+
+    /// True if `line` starts with a command word, so a malformed line gets a
+    /// syntax error rather than a conversational reply.
+    fn is_command(&self, line: &str) -> bool {
+        line.split_whitespace().next().is_some_and(|w| self.commands.find(w).is_some())
+    }
+
+    fn syntax_error(&mut self, line: &str, e: &ParseError) {
+        for (i, l) in e.render(line).into_iter().enumerate() {
+            self.push(if i == 0 { format!("MOTHER: {}", l) } else { l });
+        }
+    }
+
+    /// Completes the command word being typed; lists candidates if ambiguous.
+    fn complete(&mut self) {
+        if self.input.contains(char::is_whitespace) {
             return;
         }
+        let found = self.commands.complete(&self.input);
+        match found.as_slice() {
+            [] => {}
+            [one] => self.input = format!("{} ", one),
+            many => {
+                let common = common_prefix(many);
+                if common.len() > self.input.len() {
+                    self.input = common;
+                } else {
+                    self.push(format!("MOTHER: {}", many.join("  ")));
+                }
+            }
+        }
+    }
 
-        // ep <ok|fail|note> <summary>
-        if let Some(rest) = trimmed.strip_prefix("ep ") {
-            let mut parts = rest.splitn(2, ' ');
-            let outcome = parts.next().unwrap_or("").trim().to_lowercase();
-            let summary = parts.next().unwrap_or("").trim().to_string();
+    fn cmd_episodes(&mut self, cmd: &Command) -> Result<Vec<Action>, ParseError> {
+        let limit = count_arg(cmd, 20)?;
+        match self.db.list_episodes(limit) {
+            Ok(items) if items.is_empty() => self.push("MOTHER: No episodes stored yet."),
+            Ok(items) => {
+                self.push("MOTHER: Recent episodes:");
+                for e in items {
+                    self.push(format!("  - [{}] {}  {}", e.outcome, e.captured_at, e.summary));
+                }
+            }
+            Err(e) => self.db_error(e),
+        }
+        Ok(Vec::new())
+    }
 
-            let valid = outcome == "ok" || outcome == "fail" || outcome == "note";
-            if !valid || summary.is_empty() {
-                self.push("MOTHER: Format is: ep ok <what worked> | ep fail <what failed> | ep note <note>");
-                return;
+    fn cmd_ep(&mut self, cmd: &Command) -> Result<Vec<Action>, ParseError> {
+        let Some(outcome) = cmd.args.first() else {
+            cmd.complete()?;
+            return Err(cmd.missing("expected an outcome: ok, fail or note"));
+        };
+        let outcome_name = outcome.text.to_lowercase();
+        if !matches!(outcome_name.as_str(), "ok" | "fail" | "note") {
+            return Err(ParseError::new(outcome.pos, "outcome must be ok, fail or note"));
+        }
+        let summary = cmd.raw_after(0).to_string();
+        if summary.is_empty() {
+            return Err(cmd.missing("expected a summary after the outcome"));
+        }
+
+        match self.db.add_episode(&outcome_name, &summary) {
+            Ok(()) => {
+                if let Some(t) = self.tally.as_mut() {
+                    t.episodes += 1;
+                }
+                self.push(format!("MOTHER: EPISODE RECORDED [{}] {}", outcome_name, summary));
+                self.bus.publish(Message::EpisodeRecorded { outcome: outcome_name, summary });
             }
+            Err(e) => self.db_error(e),
+        }
+        Ok(Vec::new())
+    }
 
-            match self.db.add_episode(&outcome, &summary) {
-                Ok(()) => self.push(format!("MOTHER: EPISODE RECORDED [{}] {}", outcome, summary)),
-                Err(e) => self.push(format!("MOTHER: DB error: {}", e)),
+    fn cmd_list(&mut self, cmd: &Command) -> Result<Vec<Action>, ParseError> {
+        let limit = count_arg(cmd, 20)?;
+        match self.db.list_concepts(limit) {
+            Ok(items) if items.is_empty() => self.push("MOTHER: No concepts stored yet."),
+            Ok(items) => {
+                self.push("MOTHER: Recent concepts:");
+                for c in items {
+                    self.push(format!("  - {} (conf {:.2})", c.name, c.confidence));
+                }
             }
+            Err(e) => self.db_error(e),
+        }
+        Ok(Vec::new())
+    }
+
+    fn cmd_show(&mut self, cmd: &Command) -> Result<Vec<Action>, ParseError> {
+        let name = concept_arg(cmd)?;
+        self.bus.publish(Message::FocusConcept(name.clone()));
+        match self.db.get_concept(&name) {
+            Ok(Some(c)) => self.show_concept(&c),
+            Ok(None) => self.push(format!("MOTHER: I have no concept named '{}'.", name)),
+            Err(e) => self.db_error(e),
+        }
+        Ok(Vec::new())
+    }
+
+    fn cmd_open(&mut self, cmd: &Command) -> Result<Vec<Action>, ParseError> {
+        Ok(vec![Action::OpenConcept(concept_arg(cmd)?)])
+    }
+
+    fn cmd_learn(&mut self, cmd: &Command) -> Result<Vec<Action>, ParseError> {
+        let confidence = cmd.flag_value("confidence", 0.40f64)?;
+        if !(0.0..=1.0).contains(&confidence) {
+            let pos = cmd.flag("confidence").map_or(cmd.pos, |f| f.pos);
+            return Err(ParseError::new(pos, "confidence must be between 0 and 1"));
+        }
+        let Some(is) = cmd.args.iter().position(|t| !t.quoted && t.text == "is") else {
+            cmd.complete()?;
+            return Err(cmd.missing("expected 'is' followed by a definition"));
+        };
+        let name = cmd.args[..is].iter().map(|t| t.text.as_str()).collect::<Vec<_>>().join(" ");
+        let name = name.trim().to_lowercase();
+        if name.is_empty() {
+            return Err(ParseError::new(cmd.args[is].pos, "expected a concept name before 'is'"));
+        }
+        let definition = cmd.raw_after(is).to_string();
+        if definition.is_empty() {
+            return Err(cmd.missing("expected a definition after 'is'"));
+        }
+
+        self.bus.publish(Message::FocusConcept(name.clone()));
+        // A proposal already waiting goes back to the front of the queue.
+        if let Some(p) = self.pending.take() {
+            self.queued.push_front(p);
+        }
+        self.propose(Proposal { name, definition, confidence, replaces: None, note: None });
+        Ok(Vec::new())
+    }
+
+    /// Shows `p` for confirmation, or queues it behind the pending one.
+    fn propose(&mut self, p: Proposal) {
+        if self.pending.is_some() {
+            self.queued.push_back(p);
             return;
         }
+        self.push("MOTHER: PROPOSAL CREATED.");
+        self.push(format!("  Concept: {}", p.name));
+        if let Some(old) = &p.replaces {
+            self.push(format!("  Replaces: {}", old));
+        }
+        self.push(format!("  Definition: {}", p.definition));
+        if !self.queued.is_empty() {
+            self.push(format!("  ({} more proposal(s) queued)", self.queued.len()));
+        }
+        self.push("MOTHER: Confirm? [y]es / [n]o");
+        self.pending = Some(p);
+    }
 
-        // list concepts
-        if trimmed.eq_ignore_ascii_case("list") {
-            match self.db.list_concepts(20) {
-                Ok(items) if items.is_empty() => self.push("MOTHER: No concepts stored yet."),
-                Ok(items) => {
-                    self.push("MOTHER: Recent concepts:");
-                    for c in items {
-                        self.push(format!("  - {} (conf {:.2})", c.name, c.confidence));
-                    }
+    fn next_proposal(&mut self) {
+        if let Some(p) = self.queued.pop_front() {
+            self.propose(p);
+        }
+    }
+
+    fn cmd_rel(&mut self, cmd: &Command) -> Result<Vec<Action>, ParseError> {
+        if cmd.args.len() < 3 {
+            return Err(cmd.missing("expected <from> <type> <to>"));
+        }
+        let field = |i: usize| cmd.args[i].text.trim().to_lowercase();
+        // Trailing words all belong to the target, as in `rel jwt part_of json web tokens`.
+        let (from, relation_type, to) = (field(0), field(1), cmd.rest(2).trim().to_lowercase());
+        if let Some(i) = [&from, &relation_type, &to].iter().position(|f| f.is_empty()) {
+            return Err(ParseError::new(cmd.args[i].pos, "rel fields must be non-empty"));
+        }
+
+        self.bus.publish(Message::FocusConcept(from.clone()));
+        match self.db.upsert_relation(&from, &relation_type, &to) {
+            Ok(added) => {
+                if let Some(t) = self.tally.as_mut() {
+                    if added { t.relations_added += 1 } else { t.relations_existing += 1 }
+                }
+                if added {
+                    self.push(format!("MOTHER: Linked {} --{}--> {}", from, relation_type, to));
+                    self.bus.publish(Message::RelationAdded { from, relation_type, to });
+                } else {
+                    self.push(format!("MOTHER: Already linked: {} --{}--> {}", from, relation_type, to));
                 }
-                Err(e) => self.push(format!("MOTHER: DB error: {}", e)),
             }
-            return;
+            Err(e) => self.db_error(e),
         }
+        Ok(Vec::new())
+    }
 
-        // show <concept>
-        if let Some(rest) = trimmed.strip_prefix("show ") {
-            let name = rest.trim().to_lowercase();
-            match self.db.get_concept(&name) {
-                Ok(Some(c)) => self.show_concept(&c),
-                Ok(None) => self.push(format!("MOTHER: I have no concept named '{}'.", name)),
-                Err(e) => self.push(format!("MOTHER: DB error: {}", e)),
+    fn cmd_step(&mut self, cmd: &Command, redo: bool) -> Result<Vec<Action>, ParseError> {
+        if let Some(t) = cmd.args.first() {
+            return Err(ParseError::new(t.pos, format!("'{}' takes no arguments", cmd.name)));
+        }
+        let (verb, step) = if redo { ("redo", self.db.redo()) } else { ("undo", self.db.undo()) };
+        match step {
+            Ok(Some(label)) => {
+                self.push(format!("MOTHER: {}: {}", verb.to_uppercase(), label));
+                self.bus.publish(Message::Reverted(format!("{}: {}", verb, label)));
             }
-            return;
+            Ok(None) => self.push(format!("MOTHER: Nothing to {}.", verb)),
+            Err(e) => self.db_error(e),
         }
+        Ok(Vec::new())
+    }
 
-        // learn <concept> is <definition>
-        if let Some(rest) = trimmed.strip_prefix("learn ") {
-            let parts: Vec<&str> = rest.splitn(2, " is ").collect();
-            if parts.len() != 2 {
-                self.push("MOTHER: Format is: learn <concept> is <definition>");
-                return;
+    fn cmd_audit(&mut self, cmd: &Command) -> Result<Vec<Action>, ParseError> {
+        let limit = count_arg(cmd, 20)?;
+        let concept = text_flag(cmd, "concept")?;
+        let operator = text_flag(cmd, "operator")?;
+        match self.db.list_audit(concept, operator, limit) {
+            Ok(items) if items.is_empty() => self.push("MOTHER: No matching audit entries."),
+            Ok(items) => {
+                self.push("MOTHER: Audit log:");
+                for e in items {
+                    self.push(format!("  #{} {}  {} ({})  {}", e.id, e.at, e.operator, e.session, e.change));
+                    if !e.command.is_empty() && e.command != e.change {
+                        self.push(format!("      > {}", e.command));
+                    }
+                }
             }
+            Err(e) => self.db_error(e),
+        }
+        Ok(Vec::new())
+    }
 
-            let name = parts[0].trim().to_lowercase();
-            let definition = parts[1].trim().to_string();
+    fn cmd_export(&mut self, cmd: &Command) -> Result<Vec<Action>, ParseError> {
+        let Some(what) = cmd.args.first() else {
+            return Err(cmd.missing("expected what to export: graph or rdf"));
+        };
+        let rdf = match what.text.to_lowercase().as_str() {
+            "graph" => false,
+            "rdf" => true,
+            _ => return Err(ParseError::new(what.pos, "can only export: graph, rdf")),
+        };
+        let file = path_arg(cmd, "expected a file to write")?;
+        let target = if rdf {
+            let format = format_arg(cmd, file, RdfFormat::from_name, RdfFormat::from_path, &["turtle", "jsonld"])?;
+            Target::Rdf(format, self.rdf_base(cmd)?)
+        } else {
+            rdf_only(cmd, &["base"])?;
+            Target::Graph(format_arg(cmd, file, GraphFormat::from_name, GraphFormat::from_path, &["dot", "mermaid", "graphml"])?)
+        };
+        let around = text_flag(cmd, "around")?.map(str::to_lowercase);
+        let depth = cmd.flag_value("depth", 1usize)?;
+        if let (Some(f), None) = (cmd.flag("depth"), &around) {
+            return Err(ParseError::new(f.pos, "--depth needs --around"));
+        }
 
-            if name.is_empty() || definition.is_empty() {
-                self.push("MOTHER: Concept name and definition must be non-empty.");
-                return;
+        let graph = match export::collect(&self.db, around.as_deref(), depth) {
+            Ok(graph) => graph,
+            Err(e) => {
+                self.db_error(e);
+                return Ok(Vec::new());
             }
+        };
+        if let Some(center) = &around
+            && graph.edges.is_empty()
+            && graph.nodes.get(center).is_some_and(Option::is_none)
+        {
+            let pos = cmd.flag("around").map_or(cmd.pos, |f| f.pos);
+            return Err(ParseError::new(pos, format!("I have no concept named '{}'", center)));
+        }
+        let (text, concepts, format) = match target {
+            Target::Graph(format) => (export::render(&graph, format), graph.nodes.len(), format.name()),
+            Target::Rdf(format, base) => match rdf::collect(&self.db, &graph) {
+                Ok(knowledge) => (rdf::render(&knowledge, &base, format), knowledge.concepts.len(), format.name()),
+                Err(e) => {
+                    self.db_error(e);
+                    return Ok(Vec::new());
+                }
+            },
+        };
+        if let Err(e) = fs::write(&file.text, text) {
+            return Err(ParseError::new(file.pos, format!("cannot write {}: {}", file.text, e)));
+        }
+        self.push(format!(
+            "MOTHER: Exported {} concept(s) and {} relation(s) to {} ({}).",
+            concepts,
+            graph.edges.len(),
+            file.text,
+            format,
+        ));
+        Ok(Vec::new())
+    }
 
-            self.pending = Some(Proposal {
-                name: name.clone(),
-                definition: definition.clone(),
-                confidence: 0.40,
-            });
+    fn cmd_import(&mut self, cmd: &Command) -> Result<Vec<Action>, ParseError> {
+        let Some(what) = cmd.args.first() else {
+            return Err(cmd.missing("expected what to import: notes or rdf"));
+        };
+        match what.text.to_lowercase().as_str() {
+            "notes" => self.import_notes(cmd),
+            "rdf" => self.import_rdf(cmd),
+            _ => Err(ParseError::new(what.pos, "can only import: notes, rdf")),
+        }
+    }
 
-            self.push("MOTHER: PROPOSAL CREATED.");
-            self.push(format!("  Concept: {}", name));
-            self.push(format!("  Definition: {}", definition));
-            self.push("MOTHER: Confirm? [y]es / [n]o");
-            return;
+    /// Notes unchanged since their last import are skipped. New concepts are
+    /// stored, changed definitions of existing ones are proposed, and links
+    /// are added as relations.
+    fn import_notes(&mut self, cmd: &Command) -> Result<Vec<Action>, ParseError> {
+        rdf_only(cmd, &["format", "base"])?;
+        let dir = path_arg(cmd, "expected a directory of Markdown notes")?;
+        // Imports are remembered by canonical path, however the directory is typed.
+        let root = fs::canonicalize(&dir.text).map_err(|e| ParseError::new(dir.pos, format!("{}: {}", dir.text, e)))?;
+        let notes = notes::scan(&root).map_err(|e| ParseError::new(dir.pos, e))?;
+
+        let (mut unchanged, mut created, mut added, mut existing) = (0, 0, 0, 0);
+        let mut skipped = Vec::new();
+        let mut proposals = Vec::new();
+        for note in &notes {
+            let path = note.path.to_string_lossy();
+            let stored = (|| {
+                if self.db.note_import_hash(&path)?.as_deref() == Some(note.hash.as_str()) {
+                    unchanged += 1;
+                    return Ok(());
+                }
+                let proposed = proposals.len();
+                if note.definition.is_empty() {
+                    skipped.push(note.path.strip_prefix(&root).unwrap_or(&note.path).display().to_string());
+                } else if self.import_concept(&note.name, &note.definition, None, &mut proposals)? {
+                    created += 1;
+                }
+                for (relation_type, to) in &note.links {
+                    if self.db.upsert_relation(&note.name, relation_type, to)? {
+                        added += 1;
+                    } else {
+                        existing += 1;
+                    }
+                }
+                match proposals.get_mut(proposed) {
+                    Some(p) => {
+                        p.note = Some((path.to_string(), note.hash.clone()));
+                        Ok(())
+                    }
+                    None => self.db.record_note_import(&path, &note.name, &note.hash),
+                }
+            })();
+            if let Err(e) = stored {
+                self.db_error(e);
+                break;
+            }
+        }
+        if let Some(t) = self.tally.as_mut() {
+            t.relations_added += added;
+            t.relations_existing += existing;
         }
 
-        // rel <from> <type> <to>
-        if let Some(rest) = trimmed.strip_prefix("rel ") {
-            let parts: Vec<&str> = rest.split_whitespace().collect();
-            if parts.len() < 3 {
-                self.push("MOTHER: Format is: rel <from> <type> <to>");
-                self.push("MOTHER: Example: rel jwt used_for authentication");
-                return;
+        self.push(format!("MOTHER: IMPORTED {} note(s) from {}; {} unchanged.", notes.len(), dir.text, unchanged));
+        self.push(format!("  concepts created: {}  updates proposed: {}", created, proposals.len()));
+        self.push(format!("  relations added: {}  already present: {}", added, existing));
+        for p in &skipped {
+            self.push(format!("  skipped {}: no paragraph to use as a definition", p));
+        }
+        if created + added > 0 {
+            self.bus.publish(Message::Imported(format!("{} note(s) from {}", notes.len() - unchanged, dir.text)));
+        }
+        self.propose_all(proposals);
+        Ok(Vec::new())
+    }
+
+    /// Like `import_notes`, for resources with an rdfs:comment and triples
+    /// whose predicate is under `<base>relation/`.
+    fn import_rdf(&mut self, cmd: &Command) -> Result<Vec<Action>, ParseError> {
+        let file = path_arg(cmd, "expected a Turtle or JSON-LD file")?;
+        let format = format_arg(cmd, file, RdfFormat::from_name, RdfFormat::from_path, &["turtle", "jsonld"])?;
+        let base = self.rdf_base(cmd)?;
+        let text = fs::read_to_string(&file.text)
+            .map_err(|e| ParseError::new(file.pos, format!("cannot read {}: {}", file.text, e)))?;
+        let knowledge =
+            rdf::parse(&text, &base, format).map_err(|e| ParseError::new(file.pos, format!("{}: {}", file.text, e)))?;
+
+        let (mut created, mut added, mut existing) = (0, 0, 0);
+        let mut proposals = Vec::new();
+        let stored = (|| -> rusqlite::Result<()> {
+            for c in &knowledge.concepts {
+                if self.import_concept(&c.name, &c.definition, c.confidence, &mut proposals)? {
+                    created += 1;
+                }
             }
-            let from = parts[0].trim().to_lowercase();
-            let relation_type = parts[1].trim().to_lowercase();
-            let to = parts[2..].join(" ").trim().to_lowercase();
+            for link in &knowledge.links {
+                if self.db.upsert_relation(&link.from, &link.relation_type, &link.to)? {
+                    added += 1;
+                } else {
+                    existing += 1;
+                }
+            }
+            Ok(())
+        })();
+        if let Err(e) = stored {
+            self.db_error(e);
+        }
+        if let Some(t) = self.tally.as_mut() {
+            t.relations_added += added;
+            t.relations_existing += existing;
+        }
 
-            if from.is_empty() || relation_type.is_empty() || to.is_empty() {
-                self.push("MOTHER: rel fields must be non-empty.");
-                return;
+        self.push(format!(
+            "MOTHER: IMPORTED {} concept(s) and {} relation(s) from {} ({}).",
+            knowledge.concepts.len(),
+            knowledge.links.len(),
+            file.text,
+            format.name(),
+        ));
+        self.push(format!("  concepts created: {}  updates proposed: {}", created, proposals.len()));
+        self.push(format!("  relations added: {}  already present: {}", added, existing));
+        if created + added > 0 {
+            self.bus.publish(Message::Imported(file.text.clone()));
+        }
+        self.propose_all(proposals);
+        Ok(Vec::new())
+    }
+
+    /// Stores `name` if it is new (true); otherwise a different `definition`
+    /// becomes a proposal, keeping the stored confidence unless one is given.
+    fn import_concept(
+        &mut self,
+        name: &str,
+        definition: &str,
+        confidence: Option<f64>,
+        proposals: &mut Vec<Proposal>,
+    ) -> rusqlite::Result<bool> {
+        match self.db.get_concept(name)? {
+            None => {
+                self.db.upsert_concept(name, definition, confidence.unwrap_or(0.40))?;
+                if let Some(t) = self.tally.as_mut() {
+                    t.created.push(name.to_string());
+                }
+                return Ok(true);
             }
+            Some(c) if c.definition != definition => proposals.push(Proposal {
+                name: name.to_string(),
+                definition: definition.to_string(),
+                confidence: confidence.unwrap_or(c.confidence),
+                replaces: Some(c.definition),
+                note: None,
+            }),
+            Some(_) => {}
+        }
+        Ok(false)
+    }
 
-            match self.db.upsert_relation(&from, &relation_type, &to) {
-                Ok(()) => self.push(format!("MOTHER: Linked {} --{}--> {}", from, relation_type, to)),
-                Err(e) => self.push(format!("MOTHER: DB error: {}", e)),
+    /// Queues `proposals`, skipping any already pending or queued.
+    fn propose_all(&mut self, proposals: Vec<Proposal>) {
+        for p in proposals {
+            let same = |q: &Proposal| q.name == p.name && q.definition == p.definition;
+            if !self.pending.iter().chain(&self.queued).any(same) {
+                self.queued.push_back(p);
             }
-            return;
         }
+        if self.pending.is_none() {
+            self.next_proposal();
+        }
+    }
+
+    /// `--base`, else `rdf_base` from mother.json.
+    fn rdf_base(&self, cmd: &Command) -> Result<String, ParseError> {
+        Ok(text_flag(cmd, "base")?.map_or_else(|| self.rdf_base.clone(), str::to_string))
+    }
 
-        // fallback
-        self.push(self.eliza_reflect(trimmed));
+    fn cmd_source(&mut self, cmd: &Command) -> Result<Vec<Action>, ParseError> {
+        let path = cmd.rest(0);
+        if path.is_empty() {
+            return Err(cmd.missing("expected a file name"));
+        }
+        let opts = BatchOptions { confirm: cmd.flag("yes").is_some(), keep_going: cmd.flag("keep-going").is_some() };
+        let (s, transcript) = self.source(&path, opts).map_err(|e| ParseError::new(cmd.args[0].pos, e))?;
+        for l in transcript {
+            self.push(l);
+        }
+        if s.failed.is_empty() {
+            self.notifier.info(format!("Sourced {}: {} command(s).", path, s.commands));
+        } else {
+            self.notifier.warn(format!("Sourced {}: {} command(s) failed.", path, s.failed.len()));
+        }
+        Ok(Vec::new())
+    }
+
+    fn cmd_alias(&mut self, cmd: &Command) -> Result<Vec<Action>, ParseError> {
+        let Some(name) = cmd.args.first() else {
+            if self.commands.aliases().is_empty() {
+                self.push("MOTHER: No aliases. Define one: alias ll list 100");
+            }
+            let lines: Vec<String> = self.commands.aliases().iter().map(|(k, v)| format!("  {} = {}", k, v)).collect();
+            for l in lines {
+                self.push(l);
+            }
+            return Ok(Vec::new());
+        };
+        if cmd.args.len() < 2 {
+            return Err(cmd.missing("expected the command the alias expands to"));
+        }
+        let expansion = cmd.raw_from(1).to_string();
+        match self.commands.add_alias(&name.text, &expansion) {
+            Ok(()) => self.push(format!("MOTHER: {} = {}", name.text.to_lowercase(), expansion)),
+            Err(e) => return Err(ParseError::new(name.pos, e)),
+        }
+        Ok(Vec::new())
+    }
+
+    fn cmd_unalias(&mut self, cmd: &Command) -> Result<Vec<Action>, ParseError> {
+        let Some(name) = cmd.args.first() else {
+            return Err(cmd.missing("expected an alias name"));
+        };
+        if !self.commands.remove_alias(&name.text) {
+            return Err(ParseError::new(name.pos, format!("no alias named '{}'", name.text)));
+        }
+        self.push(format!("MOTHER: Removed alias '{}'.", name.text));
+        Ok(Vec::new())
+    }
+
+    fn cmd_help(&mut self, cmd: &Command) -> Result<Vec<Action>, ParseError> {
+        let lines = match cmd.args.first() {
+            None => self.commands.summary(),
+            Some(t) => match self.commands.help(&t.text) {
+                Some(lines) => lines,
+                None => return Err(ParseError::new(t.pos, format!("no command named '{}'", t.text))),
+            },
+        };
+        self.push("MOTHER: Commands:");
+        for l in lines {
+            self.push(l);
+        }
+        Ok(Vec::new())
     }
 
     fn show_concept(&mut self, c: &Concept) {
@@ -186,23 +989,49 @@ impl Dialog {
         self.push(format!("  Created: {}", c.created_at));
     }
 
+    /// Stores the pending proposal; the result is appended to `history`.
     fn confirm_pending(&mut self) {
         if let Some(p) = self.pending.take() {
-            match self.db.upsert_concept(&p.name, &p.definition, p.confidence) {
-                Ok(()) => {
+            // Only a running `source` reports created vs updated.
+            let existed = match &self.tally {
+                Some(_) => self.db.get_concept(&p.name).map(|c| c.is_some()),
+                None => Ok(false),
+            };
+            let stored = existed.and_then(|existed| {
+                self.db.upsert_concept(&p.name, &p.definition, p.confidence)?;
+                if let Some((path, hash)) = &p.note {
+                    self.db.record_note_import(path, &p.name, hash)?;
+                }
+                Ok(existed)
+            });
+            match stored {
+                Ok(existed) => {
+                    self.bus.publish(Message::ConceptChanged(p.name.clone()));
+                    self.bus.publish(Message::FocusConcept(p.name.clone()));
                     self.push("MOTHER: COMMITTED.");
                     self.push(format!("  Stored concept '{}'.", p.name));
+                    match self.tally.as_mut() {
+                        Some(t) if existed => t.updated.push(p.name),
+                        Some(t) => t.created.push(p.name),
+                        None => self.notifier.info(format!("Stored concept '{}'.", p.name)),
+                    }
                 }
-                Err(e) => self.push(format!("MOTHER: DB error committing proposal: {}", e)),
+                Err(e) => self.db_error(e),
             }
+            self.next_proposal();
         } else {
             self.push("MOTHER: No pending proposal.");
         }
     }
 
+    /// Drops the pending proposal; the result is appended to `history`.
     fn reject_pending(&mut self) {
-        if self.pending.take().is_some() {
+        if let Some(p) = self.pending.take() {
+            if let Some(t) = self.tally.as_mut() {
+                t.rejected.push(p.name);
+            }
             self.push("MOTHER: Proposal rejected.");
+            self.next_proposal();
         } else {
             self.push("MOTHER: No pending proposal.");
         }
@@ -210,15 +1039,39 @@ impl Dialog {
 }
 
 impl Module for Dialog {
-    fn render(&mut self, f: &mut Frame) {
+    fn bindings(&self) -> Vec<Binding> {
+        vec![
+            Binding::new(&[key(KeyCode::Enter)], "run the typed command ('help' lists them)"),
+            Binding::new(&[key(KeyCode::Tab)], "complete command name"),
+            Binding::new(&[key(KeyCode::Backspace)], "delete last character"),
+            Binding::new(&[key(KeyCode::PageUp), key(KeyCode::PageDown)], "scroll history"),
+            Binding::new(&[ch('y')], "confirm pending proposal"),
+            Binding::new(&[ch('n')], "reject pending proposal"),
+        ]
+    }
+
+    fn captures_text(&self) -> bool {
+        !self.input.is_empty()
+    }
+
+    fn render(&mut self, f: &mut Frame, area: Rect) {
         let layout = Layout::default()
             .direction(Direction::Vertical)
             .constraints([Constraint::Min(3), Constraint::Length(3)])
-            .split(f.area());
+            .split(area);
+
+        // Bottom-anchored history, offset by `scroll`.
+        let visible = layout[0].height.saturating_sub(2) as usize;
+        let max_scroll = self.history.len().saturating_sub(visible);
+        self.scroll = self.scroll.min(max_scroll);
+        let top = max_scroll - self.scroll;
+        let title = if self.scroll > 0 { format!("DIALOG (-{})", self.scroll) } else { "DIALOG".to_string() };
 
         let text = self.history.join("\n");
         let dialog = Paragraph::new(text)
-            .block(Block::default().borders(Borders::ALL).title("DIALOG"));
+            .scroll((top as u16, 0))
+            .block(Block::default().borders(Borders::ALL).title(title));
+        self.history_area = layout[0];
 
         let input = Paragraph::new(self.input.as_str())
             .block(Block::default().borders(Borders::ALL).title("INPUT"));
@@ -227,19 +1080,259 @@ impl Module for Dialog {
         f.render_widget(input, layout[1]);
     }
 
-    fn handle_input(&mut self, key: KeyEvent) {
+    fn handle_input(&mut self, key: KeyEvent) -> Vec<Action> {
         match key.code {
             KeyCode::Char('y') if self.pending.is_some() => self.confirm_pending(),
             KeyCode::Char('n') if self.pending.is_some() => self.reject_pending(),
             KeyCode::Char(c) => self.input.push(c),
             KeyCode::Backspace => { self.input.pop(); }
+            KeyCode::Tab => self.complete(),
+            KeyCode::PageUp => self.scroll += 5,
+            KeyCode::PageDown => self.scroll = self.scroll.saturating_sub(5),
             KeyCode::Enter => {
                 let line = self.input.clone();
                 self.push(format!("YOU: {}", line));
                 self.input.clear();
-                self.handle_command(&line);
+                return self.handle_command(&line);
             }
             _ => {}
         }
+        Vec::new()
+    }
+
+    fn handle_mouse(&mut self, ev: MouseEvent) -> Vec<Action> {
+        if ui::contains(self.history_area, ev.column, ev.row) {
+            match ev.kind {
+                MouseEventKind::ScrollUp => self.scroll += 3,
+                MouseEventKind::ScrollDown => self.scroll = self.scroll.saturating_sub(3),
+                _ => {}
+            }
+        }
+        Vec::new()
+    }
+
+    fn reload_config(&mut self, config: &Config) {
+        for name in self.config_aliases.keys() {
+            self.commands.remove_alias(name);
+        }
+        add_aliases(&mut self.commands, &config.aliases, &self.notifier);
+        self.config_aliases = config.aliases.clone();
+        self.rdf_base = config.rdf_base.clone();
+        self.push("MOTHER: Reloaded aliases and settings from mother.json.");
+    }
+}
+
+/// Registers aliases from mother.json, warning about ones that can't be.
+fn add_aliases(commands: &mut Registry<Dialog, Vec<Action>>, aliases: &BTreeMap<String, String>, notifier: &Notifier) {
+    for (name, expansion) in aliases {
+        if let Err(e) = commands.add_alias(name, expansion) {
+            notifier.warn(format!("DIALOG: ignoring alias: {}", e));
+        }
+    }
+}
+
+/// Optional `[count]` argument.
+fn count_arg(cmd: &Command, default: usize) -> Result<usize, ParseError> {
+    match cmd.args.as_slice() {
+        [] => Ok(default),
+        [t] => t.text.parse().map_err(|_| ParseError::new(t.pos, "count must be a whole number")),
+        [_, extra, ..] => Err(ParseError::new(extra.pos, "expected at most one count")),
+    }
+}
+
+/// `--name=<text>`, or None when the flag is absent.
+fn text_flag<'a>(cmd: &'a Command, name: &str) -> Result<Option<&'a str>, ParseError> {
+    match cmd.flag(name) {
+        Some(f) => match f.value.as_deref() {
+            Some(v) if !v.is_empty() => Ok(Some(v)),
+            _ => Err(ParseError::new(f.pos, format!("--{} needs a value: --{}=<name>", name, name))),
+        },
+        None => Ok(None),
+    }
+}
+
+/// What `export` writes.
+enum Target {
+    Graph(GraphFormat),
+    // With the base IRI.
+    Rdf(RdfFormat, String),
+}
+
+/// The path after `export`/`import`'s first word.
+fn path_arg<'a>(cmd: &'a Command, missing: &str) -> Result<&'a Token, ParseError> {
+    let Some(path) = cmd.args.get(1) else {
+        return Err(cmd.missing(missing));
+    };
+    if let Some(extra) = cmd.args.get(2) {
+        return Err(ParseError::new(extra.pos, "too many words; quote a path with spaces"));
+    }
+    Ok(path)
+}
+
+/// `--format`, else the format `file`'s extension suggests.
+fn format_arg<F>(
+    cmd: &Command,
+    file: &Token,
+    from_name: fn(&str) -> Option<F>,
+    from_path: fn(&str) -> Option<F>,
+    names: &[&str],
+) -> Result<F, ParseError> {
+    match cmd.flag("format") {
+        Some(f) => f.value.as_deref().and_then(from_name).ok_or_else(|| {
+            let (last, rest) = names.split_last().expect("at least one format");
+            ParseError::new(f.pos, format!("--format must be {} or {}", rest.join(", "), last))
+        }),
+        None => from_path(&file.text)
+            .ok_or_else(|| ParseError::new(file.pos, format!("unknown extension; add --format={}", names.join("|")))),
+    }
+}
+
+/// Rejects `flags`, which only mean something for RDF.
+fn rdf_only(cmd: &Command, flags: &[&str]) -> Result<(), ParseError> {
+    match flags.iter().find_map(|name| cmd.flag(name)) {
+        Some(f) => Err(ParseError::new(f.pos, format!("--{} only applies to rdf", f.name))),
+        None => Ok(()),
+    }
+}
+
+/// Longest prefix shared by `words`, in whole chars.
+fn common_prefix(words: &[String]) -> String {
+    let first = &words[0];
+    let len = words[1..].iter().fold(first.chars().count(), |len, w| {
+        first.chars().zip(w.chars()).take(len).take_while(|(a, b)| a == b).count()
+    });
+    first.chars().take(len).collect()
+}
+
+/// All args as one lowercased concept name.
+fn concept_arg(cmd: &Command) -> Result<String, ParseError> {
+    let name = cmd.rest(0).trim().to_lowercase();
+    if name.is_empty() {
+        return Err(cmd.missing("expected a concept name"));
+    }
+    Ok(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::Scratch;
+
+    #[test]
+    fn common_prefix_keeps_whole_chars() {
+        let words = |ws: &[&str]| ws.iter().map(|w| w.to_string()).collect::<Vec<_>>();
+        // 'é' and 'è' share their first UTF-8 byte.
+        assert_eq!(common_prefix(&words(&["xé", "xè"])), "x");
+        assert_eq!(common_prefix(&words(&["café-au-lait", "café-crème", "café-noir"])), "café-");
+        assert_eq!(common_prefix(&words(&["list", "learn"])), "l");
+        assert_eq!(common_prefix(&words(&["show"])), "show");
+    }
+
+    #[test]
+    fn reload_replaces_configured_aliases_only() {
+        let aliases = |pairs: &[(&str, &str)]| pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+        let config = Config { aliases: aliases(&[("ll", "list 100"), ("old", "list 1")]), ..Config::default() };
+        let db = Database::init(":memory:").unwrap();
+        let mut dialog = Dialog::new(db, Notifier::default(), Bus::default(), &config);
+        dialog.run_line("alias mine list 5").unwrap();
+
+        let reloaded = Config {
+            aliases: aliases(&[("ll", "list 200"), ("new", "list 2")]),
+            rdf_base: "urn:reloaded:".into(),
+            ..Config::default()
+        };
+        dialog.reload_config(&reloaded);
+        let now: Vec<_> = dialog.commands.aliases().iter().map(|(k, v)| (k.as_str(), v.as_str())).collect();
+        assert_eq!(now, [("ll", "list 200"), ("mine", "list 5"), ("new", "list 2")]);
+        assert_eq!(dialog.rdf_base, "urn:reloaded:");
+    }
+
+    #[test]
+    fn source_reports_failures_past_the_scrollback_cap() {
+        let text: String = (1..=300).map(|i| format!("bogus {}\n", i)).collect();
+        let script = Scratch::file("dialog-long.txt", &text);
+        let db = Database::init(":memory:").unwrap();
+        let mut dialog = Dialog::new(db, Notifier::default(), Bus::default(), &Config::default());
+
+        let opts = BatchOptions { keep_going: true, ..BatchOptions::default() };
+        let (summary, transcript) = dialog.source(&script.string(), opts).unwrap();
+        assert_eq!(summary.failed.len(), 300);
+        let report = summary.lines(&script.string());
+        assert_eq!(transcript.len(), 300 * 2 + report.len());
+        for (i, pair) in transcript.chunks(2).take(300).enumerate() {
+            assert_eq!(pair[0], format!("MOTHER: {}:{}: bogus {}", script.string(), i + 1, i + 1));
+            assert_eq!(pair[1], "MOTHER: Not a command. Type 'help' for the list.");
+        }
+        assert_eq!(transcript[600..], report[..]);
+    }
+
+    #[test]
+    fn source_counts_failed_confirms() {
+        let file = Scratch::new("dialog-confirm.db");
+        let db = Database::init(&file.string()).unwrap();
+        rusqlite::Connection::open(file.path())
+            .unwrap()
+            .execute_batch("CREATE TRIGGER refuse BEFORE INSERT ON concepts BEGIN SELECT RAISE(ABORT, 'refused'); END;")
+            .unwrap();
+        let script = Scratch::file("dialog-confirm.txt", "learn jwt is a signed token\nep ok it worked\n");
+        let mut dialog = Dialog::new(db, Notifier::default(), Bus::default(), &Config::default());
+
+        let opts = BatchOptions { confirm: true, keep_going: true };
+        let (summary, _) = dialog.source(&script.string(), opts).unwrap();
+        assert_eq!(summary.failed, [1]);
+        assert!(summary.created.is_empty());
+        assert_eq!((summary.commands, summary.episodes), (1, 1));
+    }
+
+    #[test]
+    fn notes_are_remembered_however_the_directory_is_typed() {
+        let dir = Scratch::dir("dialog-notes");
+        fs::write(dir.join("jwt.md"), "# jwt\n\nA signed token.\n").unwrap();
+        let db = Database::init(":memory:").unwrap();
+        let mut dialog = Dialog::new(db, Notifier::default(), Bus::default(), &Config::default());
+
+        dialog.run_line(&format!("import notes \"{}\"", dir.string())).unwrap();
+        let again = dialog.run_line(&format!("import notes \"{}/./\"", dir.string())).unwrap();
+        assert!(again[0].ends_with("; 1 unchanged."), "{:?}", again);
+    }
+
+    #[test]
+    fn decide_returns_its_own_transcript() {
+        let db = Database::init(":memory:").unwrap();
+        let mut dialog = Dialog::new(db, Notifier::default(), Bus::default(), &Config::default());
+        let shown = dialog.history().len();
+        for i in 0..150 {
+            dialog.run_line(&format!("learn c{} is concept {}", i, i)).unwrap();
+            let out = dialog.decide(i % 2 == 0);
+            let expected = if i % 2 == 0 { "MOTHER: COMMITTED." } else { "MOTHER: Proposal rejected." };
+            assert_eq!(out[0], expected, "{:?}", out);
+        }
+        assert_eq!(dialog.history().len(), shown);
+    }
+
+    #[test]
+    fn rejected_note_updates_are_offered_again() {
+        let dir = Scratch::dir("dialog-updates");
+        let note = dir.join("jwt.md");
+        fs::write(&note, "# jwt\n\nA signed token.\n").unwrap();
+        let db = Database::init(":memory:").unwrap();
+        let mut dialog = Dialog::new(db, Notifier::default(), Bus::default(), &Config::default());
+        let import = format!("import notes \"{}\"", dir.string());
+
+        dialog.run_line(&import).unwrap();
+        fs::write(&note, "# jwt\n\nA compact signed token.\n").unwrap();
+        dialog.run_line(&import).unwrap();
+        assert_eq!(dialog.pending(), Some("jwt"));
+        // Importing again while it is pending doesn't queue it twice.
+        dialog.run_line(&import).unwrap();
+        dialog.decide(false);
+        assert_eq!(dialog.pending(), None);
+
+        let again = dialog.run_line(&import).unwrap();
+        assert!(again[0].ends_with("; 0 unchanged."), "{:?}", again);
+        assert_eq!(dialog.pending(), Some("jwt"));
+        dialog.decide(true);
+        let last = dialog.run_line(&import).unwrap();
+        assert!(last[0].ends_with("; 1 unchanged."), "{:?}", last);
     }
 }
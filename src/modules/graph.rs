@@ -1,27 +1,177 @@
-use crossterm::event::{KeyCode, KeyEvent};
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use crossterm::event::{KeyCode, KeyEvent, MouseButton, MouseEvent, MouseEventKind};
 use ratatui::{
-    layout::{Constraint, Direction, Layout},
-    widgets::{Block, Borders, List, ListItem, Paragraph},
+    layout::{Constraint, Direction, Layout, Rect},
+    widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph, Wrap},
     Frame,
 };
 
-use super::Module;
-use crate::db::{Database, Relation};
+use super::{Action, Module};
+use crate::bus::{Bus, Message};
+use crate::db::{Concept, Database, Episode, Relation};
+use crate::keymap::{ch, key, Binding};
+use crate::ui::{self, notify::Notifier};
+
+const HINTS: &str = "[Enter] follow  [b]ack [f]orward  [e]dit  [?] keys";
+const BREADCRUMB_LEN: usize = 6;
+const MAX_DEPTH: u8 = 3;
+const MENTION_LIMIT: usize = 5;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Pane {
+    Concepts,
+    Relations,
+    Types,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum EdgeDir {
+    Both,
+    Outgoing,
+    Incoming,
+}
+
+impl EdgeDir {
+    fn next(self) -> Self {
+        match self {
+            EdgeDir::Both => EdgeDir::Outgoing,
+            EdgeDir::Outgoing => EdgeDir::Incoming,
+            EdgeDir::Incoming => EdgeDir::Both,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            EdgeDir::Both => "both",
+            EdgeDir::Outgoing => "out",
+            EdgeDir::Incoming => "in",
+        }
+    }
+}
+
+struct Filter {
+    hidden_types: BTreeSet<String>,
+    direction: EdgeDir,
+    depth: u8,
+    min_confidence: f64,
+}
+
+impl Default for Filter {
+    fn default() -> Self {
+        Self {
+            hidden_types: BTreeSet::new(),
+            direction: EdgeDir::Both,
+            depth: 1,
+            min_confidence: 0.0,
+        }
+    }
+}
+
+impl Filter {
+    fn summary(&self) -> String {
+        let types = if self.hidden_types.is_empty() {
+            "all".to_string()
+        } else {
+            self.hidden_types
+                .iter()
+                .map(|t| format!("-{}", t))
+                .collect::<Vec<_>>()
+                .join(",")
+        };
+        format!(
+            "FILTER: dir={} depth={} conf>={:.1} types={}",
+            self.direction.label(),
+            self.depth,
+            self.min_confidence,
+            types
+        )
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum EditField {
+    Definition,
+    Confidence,
+}
+
+/// Modal form editing the focused concept in place.
+struct EditForm {
+    name: String,
+    definition: String,
+    confidence: String,
+    field: EditField,
+    error: Option<String>,
+}
+
+/// A relation reached while expanding the neighborhood of the focus.
+/// `via` is the node it was expanded from, `target` the node it leads to.
+struct Edge {
+    rel: Relation,
+    depth: u8,
+    via: String,
+    target: String,
+}
 
 pub struct Graph {
     db: Database,
+    notifier: Notifier,
+    bus: Bus,
+    // Between on_enter and on_leave; hidden, it only follows on re-entry.
+    visible: bool,
     concepts: Vec<String>,
     selected: usize,
-    status: String,
+    pane: Pane,
+    // Concept whose relations are shown; may be a dangling name not in `concepts`.
+    focus: Option<String>,
+    edges: Vec<Edge>,
+    edge_selected: usize,
+    back: Vec<String>,
+    forward: Vec<String>,
+    filter: Filter,
+    confidences: HashMap<String, f64>,
+    types: Vec<(String, usize)>,
+    type_selected: usize,
+    // Record of the focus (None when dangling) and episodes mentioning it.
+    detail: Option<Concept>,
+    mentions: Vec<Episode>,
+    editing: Option<EditForm>,
+    // Last frame's geometry, for mouse hit-testing.
+    list_state: ListState,
+    list_area: Rect,
+    rel_area: Rect,
+    rel_scroll: usize,
+    // Edge index shown on each line of the relations pane.
+    rel_lines: Vec<Option<usize>>,
 }
 
 impl Graph {
-    pub fn new(db: Database) -> Self {
+    pub fn new(db: Database, notifier: Notifier, bus: Bus) -> Self {
         let mut g = Self {
             db,
+            notifier,
+            bus,
+            visible: false,
             concepts: Vec::new(),
             selected: 0,
-            status: "GRAPH READY. Use ↑/↓. [Ctrl+C] CONSOLE [Ctrl+D] DIALOG [Ctrl+Q] QUIT".to_string(),
+            pane: Pane::Concepts,
+            focus: None,
+            edges: Vec::new(),
+            edge_selected: 0,
+            back: Vec::new(),
+            forward: Vec::new(),
+            filter: Filter::default(),
+            confidences: HashMap::new(),
+            types: Vec::new(),
+            type_selected: 0,
+            detail: None,
+            mentions: Vec::new(),
+            editing: None,
+            list_state: ListState::default(),
+            list_area: Rect::default(),
+            rel_area: Rect::default(),
+            rel_scroll: 0,
+            rel_lines: Vec::new(),
         };
         g.refresh();
         g
@@ -35,100 +185,649 @@ impl Graph {
                     self.selected = self.concepts.len().saturating_sub(1);
                 }
             }
-            Err(e) => self.status = format!("DB error: {}", e),
+            Err(e) => self.notifier.error(format!("GRAPH: DB error: {}", e)),
+        }
+        match self.db.concept_confidences() {
+            Ok(map) => self.confidences = map,
+            Err(e) => self.notifier.error(format!("GRAPH: DB error: {}", e)),
+        }
+        match self.db.list_relation_types() {
+            Ok(types) => {
+                self.types = types;
+                if self.type_selected >= self.types.len() {
+                    self.type_selected = self.types.len().saturating_sub(1);
+                }
+            }
+            Err(e) => self.notifier.error(format!("GRAPH: DB error: {}", e)),
+        }
+        if self.focus.is_none() {
+            self.focus = self.selected_name().map(str::to_string);
+        }
+        self.load_detail();
+        self.load_edges(self.current_edge_id());
+    }
+
+    fn load_detail(&mut self) {
+        self.detail = None;
+        self.mentions.clear();
+        let Some(name) = self.focus.as_deref() else { return };
+        let loaded = self.db.get_concept(name).and_then(|c| {
+            Ok((c, self.db.episodes_mentioning(name, MENTION_LIMIT)?))
+        });
+        match loaded {
+            Ok((c, eps)) => {
+                self.detail = c;
+                self.mentions = eps;
+            }
+            Err(e) => self.notifier.error(format!("GRAPH: DB error: {}", e)),
+        }
+    }
+
+    fn open_editor(&mut self) {
+        let Some(name) = self.focus.clone() else { return };
+        // A dangling focus gets a blank form; saving creates the concept.
+        let (definition, confidence) = match &self.detail {
+            Some(c) => (c.definition.clone(), c.confidence),
+            None => (String::new(), 0.40),
+        };
+        self.editing = Some(EditForm {
+            name,
+            definition,
+            confidence: format!("{:.2}", confidence),
+            field: EditField::Definition,
+            error: None,
+        });
+    }
+
+    fn save_editor(&mut self) {
+        let Some(form) = self.editing.as_mut() else { return };
+        let definition = form.definition.trim().to_string();
+        if definition.is_empty() {
+            form.error = Some("Definition must be non-empty.".into());
+            return;
+        }
+        let confidence = match form.confidence.trim().parse::<f64>() {
+            Ok(c) if (0.0..=1.0).contains(&c) => c,
+            _ => {
+                form.error = Some("Confidence must be a number between 0 and 1.".into());
+                return;
+            }
+        };
+        self.db.set_command(&format!("graph edit {}", form.name));
+        match self.db.upsert_concept(&form.name, &definition, confidence) {
+            Ok(()) => {
+                self.notifier.info(format!("Saved concept '{}'.", form.name));
+                self.bus.publish(Message::ConceptChanged(form.name.clone()));
+                self.editing = None;
+                self.refresh();
+            }
+            Err(e) => form.error = Some(format!("DB error: {}", e)),
+        }
+    }
+
+    fn handle_editor_input(&mut self, key: KeyEvent) {
+        let Some(form) = self.editing.as_mut() else { return };
+        let buf = match form.field {
+            EditField::Definition => &mut form.definition,
+            EditField::Confidence => &mut form.confidence,
+        };
+        match key.code {
+            KeyCode::Esc => self.editing = None,
+            KeyCode::Enter => self.save_editor(),
+            KeyCode::Tab | KeyCode::BackTab | KeyCode::Up | KeyCode::Down => {
+                form.field = match form.field {
+                    EditField::Definition => EditField::Confidence,
+                    EditField::Confidence => EditField::Definition,
+                };
+            }
+            KeyCode::Backspace => {
+                buf.pop();
+            }
+            KeyCode::Char(c) => buf.push(c),
+            _ => {}
         }
     }
 
+    fn render_detail(&self) -> String {
+        let Some(name) = self.focus.as_deref() else {
+            return String::new();
+        };
+        let mut out = match &self.detail {
+            Some(c) => format!(
+                "Name: {}\nDefinition: {}\nConfidence: {:.2}\nCreated: {}\n",
+                c.name, c.definition, c.confidence, c.created_at
+            ),
+            None => format!("Name: {}\n(no concept record; [e] to define it)\n", name),
+        };
+        out.push_str("\nMentioned in:\n");
+        if self.mentions.is_empty() {
+            out.push_str("  (no episodes)\n");
+        }
+        for e in &self.mentions {
+            out.push_str(&format!("  - [{}] {}\n", e.outcome, e.summary));
+        }
+        out
+    }
+
+    /// Reloads from the database and jumps to `name`, recording history.
+    fn focus_on(&mut self, name: &str) {
+        self.refresh();
+        self.visit(name.to_string());
+    }
+
     fn selected_name(&self) -> Option<&str> {
         self.concepts.get(self.selected).map(|s| s.as_str())
     }
+
+    fn current_edge_id(&self) -> Option<i64> {
+        self.edges.get(self.edge_selected).map(|e| e.rel.id)
+    }
+
+    /// Loads the filtered neighborhood of the focused concept, breadth first.
+    /// Within each depth, outgoing edges come before incoming ones.
+    /// The cursor stays on relation `keep` when it is still listed.
+    fn load_edges(&mut self, keep: Option<i64>) {
+        self.edges.clear();
+        let Some(focus) = self.focus.clone() else {
+            self.edge_selected = 0;
+            return;
+        };
+
+        let mut seen_rels = HashSet::new();
+        let mut visited = HashSet::from([focus.clone()]);
+        let mut frontier = vec![focus];
+
+        'levels: for depth in 1..=self.filter.depth {
+            let mut outgoing = Vec::new();
+            let mut incoming = Vec::new();
+            let mut next = Vec::new();
+
+            for node in &frontier {
+                let rels = match self.db.list_relations_for(node, 200) {
+                    Ok(rels) => rels,
+                    Err(e) => {
+                        self.notifier.error(format!("GRAPH: DB error: {}", e));
+                        break 'levels;
+                    }
+                };
+                for rel in rels {
+                    let is_out = rel.from == *node;
+                    let target = if is_out { rel.to.clone() } else { rel.from.clone() };
+                    if !self.edge_passes(&rel, is_out, &target) || !seen_rels.insert(rel.id) {
+                        continue;
+                    }
+                    if visited.insert(target.clone()) {
+                        next.push(target.clone());
+                    }
+                    let edge = Edge { rel, depth, via: node.clone(), target };
+                    if is_out { outgoing.push(edge) } else { incoming.push(edge) }
+                }
+            }
+
+            self.edges.extend(outgoing);
+            self.edges.extend(incoming);
+            frontier = next;
+            if frontier.is_empty() {
+                break;
+            }
+        }
+
+        if let Some(i) = keep.and_then(|id| self.edges.iter().position(|e| e.rel.id == id)) {
+            self.edge_selected = i;
+        }
+        if self.edge_selected >= self.edges.len() {
+            self.edge_selected = self.edges.len().saturating_sub(1);
+        }
+    }
+
+    fn edge_passes(&self, rel: &Relation, is_out: bool, target: &str) -> bool {
+        if self.filter.hidden_types.contains(&rel.relation_type) {
+            return false;
+        }
+        let dir_ok = match self.filter.direction {
+            EdgeDir::Both => true,
+            EdgeDir::Outgoing => is_out,
+            EdgeDir::Incoming => !is_out,
+        };
+        // Dangling endpoints have no confidence and only pass an unset threshold.
+        let conf = self.confidences.get(target).copied().unwrap_or(0.0);
+        dir_ok && conf >= self.filter.min_confidence
+    }
+
+    fn apply_filter(&mut self) {
+        self.load_edges(self.current_edge_id());
+    }
+
+    /// Sets the focus without touching history (list browsing).
+    fn set_focus(&mut self, name: Option<String>) {
+        if let Some(n) = &name
+            && let Some(i) = self.concepts.iter().position(|c| c == n)
+        {
+            self.selected = i;
+        }
+        // Keep the cursor on the edge we arrived by, if the new focus lists it.
+        let came_by = self.current_edge_id();
+        self.focus = name;
+        self.edge_selected = 0;
+        self.load_detail();
+        self.load_edges(came_by);
+    }
+
+    /// Jumps to `name`, recording the current focus on the back stack.
+    fn visit(&mut self, name: String) {
+        if self.focus.as_deref() == Some(name.as_str()) {
+            return;
+        }
+        if let Some(cur) = self.focus.take() {
+            self.back.push(cur);
+        }
+        self.forward.clear();
+        self.set_focus(Some(name));
+    }
+
+    fn go_back(&mut self) {
+        if let Some(prev) = self.back.pop() {
+            if let Some(cur) = self.focus.take() {
+                self.forward.push(cur);
+            }
+            self.set_focus(Some(prev));
+        }
+    }
+
+    fn go_forward(&mut self) {
+        if let Some(next) = self.forward.pop() {
+            if let Some(cur) = self.focus.take() {
+                self.back.push(cur);
+            }
+            self.set_focus(Some(next));
+        }
+    }
+
+    fn follow_selected_edge(&mut self) {
+        if let Some(e) = self.edges.get(self.edge_selected) {
+            self.visit(e.target.clone());
+        }
+    }
+
+    fn toggle_selected_type(&mut self) {
+        if let Some((t, _)) = self.types.get(self.type_selected)
+            && !self.filter.hidden_types.remove(t)
+        {
+            self.filter.hidden_types.insert(t.clone());
+        }
+        self.apply_filter();
+    }
+
+    /// Hides every type except the selected one.
+    fn only_selected_type(&mut self) {
+        if let Some((keep, _)) = self.types.get(self.type_selected) {
+            self.filter.hidden_types = self.types
+                .iter()
+                .map(|(t, _)| t.clone())
+                .filter(|t| t != keep)
+                .collect();
+        }
+        self.apply_filter();
+    }
+
+    fn breadcrumb(&self) -> String {
+        let mut trail: Vec<&str> = self.back.iter().map(|s| s.as_str()).collect();
+        if let Some(f) = &self.focus {
+            trail.push(f);
+        }
+        let skip = trail.len().saturating_sub(BREADCRUMB_LEN);
+        let mut out = if skip > 0 { "… › ".to_string() } else { String::new() };
+        out.push_str(&trail[skip..].join(" › "));
+        out
+    }
+
+    fn render_types(&self) -> String {
+        let mut out = String::from("RELATION TYPES  [Space] toggle  [o]nly  [a]ll  [Esc] back\n\n");
+        if self.types.is_empty() {
+            out.push_str("No relations stored yet.\n");
+        }
+        for (i, (t, n)) in self.types.iter().enumerate() {
+            let mark = if i == self.type_selected { ">" } else { " " };
+            let check = if self.filter.hidden_types.contains(t) { " " } else { "x" };
+            out.push_str(&format!("{} [{}] {} ({})\n", mark, check, t, n));
+        }
+        out
+    }
 }
 
 impl Module for Graph {
-    fn render(&mut self, f: &mut Frame) {
+    fn bindings(&self) -> Vec<Binding> {
+        vec![
+            Binding::new(&[key(KeyCode::Up), key(KeyCode::Down)], "move in the active pane"),
+            Binding::new(&[key(KeyCode::Tab)], "switch CONCEPTS / RELATIONS pane"),
+            Binding::new(&[key(KeyCode::Enter)], "focus concept / follow relation"),
+            Binding::new(&[ch('b'), key(KeyCode::Backspace)], "back"),
+            Binding::new(&[ch('f')], "forward"),
+            Binding::new(&[ch('e')], "edit focused concept"),
+            Binding::new(&[ch('r')], "reload from database"),
+            Binding::new(&[ch('t')], "relation type filter (Space toggle, o only, a all)"),
+            Binding::new(&[ch('d')], "cycle direction filter"),
+            Binding::new(&[ch('1'), ch('2'), ch('3')], "neighborhood depth"),
+            Binding::new(&[ch('+'), ch('-')], "raise / lower minimum confidence"),
+            Binding::new(&[ch('x')], "clear filters"),
+        ]
+    }
+
+    fn captures_text(&self) -> bool {
+        self.editing.is_some()
+    }
+
+    fn render(&mut self, f: &mut Frame, area: Rect) {
         let chunks = Layout::default()
             .direction(Direction::Vertical)
-            .constraints([Constraint::Length(3), Constraint::Min(1)])
-            .split(f.area());
+            .constraints([Constraint::Length(5), Constraint::Min(1)])
+            .split(area);
 
         let body = Layout::default()
             .direction(Direction::Horizontal)
             .constraints([Constraint::Percentage(40), Constraint::Percentage(60)])
             .split(chunks[1]);
 
-        // Header / status
-        let header = Paragraph::new(self.status.as_str())
-            .block(Block::default().borders(Borders::ALL).title("MOTHER / GRAPH"));
+        // Header / status + breadcrumb trail + active filters
+        let header = Paragraph::new(format!(
+            "{}\nPATH: {}\n{}",
+            HINTS,
+            self.breadcrumb(),
+            self.filter.summary()
+        ))
+        .block(Block::default().borders(Borders::ALL).title("MOTHER / GRAPH"));
         f.render_widget(header, chunks[0]);
 
         // Left: concept list
         let items: Vec<ListItem> = self.concepts
             .iter()
-            .enumerate()
-            .map(|(i, name)| {
-                if i == self.selected {
-                    ListItem::new(format!("> {}", name))
-                } else {
-                    ListItem::new(format!("  {}", name))
-                }
-            })
+            .map(|name| ListItem::new(name.as_str()))
             .collect();
 
         let list = List::new(items)
-            .block(Block::default().borders(Borders::ALL).title("CONCEPTS"));
+            .highlight_symbol("> ")
+            .block(Block::default().borders(Borders::ALL).title(pane_title("CONCEPTS", self.pane == Pane::Concepts)));
 
-        f.render_widget(list, body[0]);
+        self.list_state.select((!self.concepts.is_empty()).then_some(self.selected));
+        f.render_stateful_widget(list, body[0], &mut self.list_state);
+        self.list_area = body[0];
 
-        // Right: relations for selected concept
-        let right_text = if let Some(name) = self.selected_name() {
-            match self.db.list_relations_for(name, 200) {
-                Ok(rels) => render_relations(name, &rels),
-                Err(e) => format!("DB error: {}\n", e),
-            }
+        // Right: relations for focused concept, or the type picker
+        self.rel_lines.clear();
+        let mut cursor_line = None;
+        let (title, right_text) = if self.pane == Pane::Types {
+            ("[TYPES]".to_string(), self.render_types())
+        } else if let Some(name) = self.focus.as_deref() {
+            let cursor = (self.pane == Pane::Relations).then_some(self.edge_selected);
+            let (text, lines) = render_relations(name, &self.edges, cursor);
+            cursor_line = cursor.and_then(|c| lines.iter().position(|l| *l == Some(c)));
+            self.rel_lines = lines;
+            (pane_title("RELATIONS", self.pane == Pane::Relations), text)
         } else {
-            "No concepts found.\nGo to DIALOG and add one using:\nlearn <concept> is <definition>\n".to_string()
+            (
+                "RELATIONS".to_string(),
+                "No concepts found.\nGo to DIALOG and add one using:\nlearn <concept> is <definition>\n".to_string(),
+            )
         };
 
+        let right = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(13), Constraint::Min(3)])
+            .split(body[1]);
+
+        let detail = Paragraph::new(self.render_detail())
+            .wrap(Wrap { trim: false })
+            .block(Block::default().borders(Borders::ALL).title("DETAIL"));
+        f.render_widget(detail, right[0]);
+
+        // Scroll just enough to keep the cursor line visible.
+        let visible = right[1].height.saturating_sub(2) as usize;
+        if let Some(line) = cursor_line {
+            if line < self.rel_scroll {
+                self.rel_scroll = line;
+            } else if line >= self.rel_scroll + visible {
+                self.rel_scroll = line + 1 - visible;
+            }
+        }
+        let total = right_text.lines().count();
+        self.rel_scroll = self.rel_scroll.min(total.saturating_sub(visible));
+
         let rel_view = Paragraph::new(right_text)
-            .block(Block::default().borders(Borders::ALL).title("RELATIONS"));
+            .scroll((self.rel_scroll as u16, 0))
+            .block(Block::default().borders(Borders::ALL).title(title));
 
-        f.render_widget(rel_view, body[1]);
+        f.render_widget(rel_view, right[1]);
+        self.rel_area = right[1];
+
+        if let Some(form) = &self.editing {
+            render_editor(f, area, form);
+        }
     }
 
-    fn handle_input(&mut self, key: KeyEvent) {
-        match key.code {
-            KeyCode::Up => {
-                if self.selected > 0 {
-                    self.selected -= 1;
+    fn on_enter(&mut self) {
+        // Other screens may have written concepts or relations meanwhile.
+        self.visible = true;
+        self.refresh();
+    }
+
+    fn on_leave(&mut self) {
+        self.visible = false;
+    }
+
+    fn on_message(&mut self, _from: &str, msg: &Message) {
+        if !self.visible {
+            return;
+        }
+        match msg {
+            Message::FocusConcept(name) => self.focus_on(name),
+            Message::ConceptChanged(_) | Message::RelationAdded { .. } | Message::Reverted(_) | Message::Imported(_) => {
+                self.refresh()
+            }
+            _ => {}
+        }
+    }
+
+    fn focus_concept(&mut self, name: &str) -> bool {
+        self.focus_on(name);
+        true
+    }
+
+    fn handle_mouse(&mut self, ev: MouseEvent) -> Vec<Action> {
+        if self.editing.is_some() {
+            return Vec::new();
+        }
+        let (col, row) = (ev.column, ev.row);
+        let in_list = ui::contains(self.list_area, col, row);
+        let in_rels = ui::contains(self.rel_area, col, row);
+
+        match ev.kind {
+            MouseEventKind::Down(MouseButton::Left) if in_list => {
+                let line = (row - self.list_area.y) as usize;
+                if line == 0 {
+                    return Vec::new(); // border
+                }
+                let i = self.list_state.offset() + line - 1;
+                if i < self.concepts.len() {
+                    self.pane = Pane::Concepts;
+                    self.selected = i;
+                    self.set_focus(self.selected_name().map(str::to_string));
+                }
+            }
+            MouseEventKind::Down(MouseButton::Left) if in_rels && self.pane != Pane::Types => {
+                let line = (row - self.rel_area.y) as usize;
+                if line == 0 {
+                    return Vec::new();
                 }
+                let Some(Some(i)) = self.rel_lines.get(self.rel_scroll + line - 1).copied() else {
+                    return Vec::new();
+                };
+                self.pane = Pane::Relations;
+                self.edge_selected = i;
+                // Line layout: "{mark} {from} --{type}--> {to}"
+                let e = &self.edges[i].rel;
+                let x = col.saturating_sub(self.rel_area.x + 1) as usize;
+                let from_end = 2 + e.from.chars().count();
+                let to_start = from_end + 3 + e.relation_type.chars().count() + 4;
+                if (2..from_end).contains(&x) {
+                    self.visit(e.from.clone());
+                } else if x >= to_start && x < to_start + e.to.chars().count() {
+                    self.visit(e.to.clone());
+                }
+            }
+            MouseEventKind::ScrollUp | MouseEventKind::ScrollDown => {
+                let code = if ev.kind == MouseEventKind::ScrollUp { KeyCode::Up } else { KeyCode::Down };
+                if in_list {
+                    self.pane = Pane::Concepts;
+                } else if in_rels && self.pane != Pane::Types {
+                    self.pane = Pane::Relations;
+                } else if !in_rels {
+                    return Vec::new();
+                }
+                return self.handle_input(KeyEvent::from(code));
+            }
+            _ => {}
+        }
+        Vec::new()
+    }
+
+    fn handle_input(&mut self, key: KeyEvent) -> Vec<Action> {
+        if self.editing.is_some() {
+            self.handle_editor_input(key);
+            return Vec::new();
+        }
+
+        match (self.pane, key.code) {
+            (Pane::Types, KeyCode::Esc | KeyCode::Tab | KeyCode::Char('t')) => self.pane = Pane::Relations,
+            (Pane::Types, KeyCode::Up) if self.type_selected > 0 => self.type_selected -= 1,
+            (Pane::Types, KeyCode::Down) if self.type_selected + 1 < self.types.len() => {
+                self.type_selected += 1;
+            }
+            (Pane::Types, KeyCode::Char(' ') | KeyCode::Enter) => self.toggle_selected_type(),
+            (Pane::Types, KeyCode::Char('o')) => self.only_selected_type(),
+            (Pane::Types, KeyCode::Char('a')) => {
+                self.filter.hidden_types.clear();
+                self.apply_filter();
             }
-            KeyCode::Down => {
-                if self.selected + 1 < self.concepts.len() {
-                    self.selected += 1;
+            (Pane::Types, _) => {}
+
+            (_, KeyCode::Tab) => {
+                self.pane = match self.pane {
+                    Pane::Concepts => Pane::Relations,
+                    _ => Pane::Concepts,
+                };
+            }
+            (_, KeyCode::Char('b') | KeyCode::Backspace) => self.go_back(),
+            (_, KeyCode::Char('f')) => self.go_forward(),
+            (_, KeyCode::Char('r')) => self.refresh(),
+            (_, KeyCode::Char('e')) => self.open_editor(),
+
+            // Filters
+            (_, KeyCode::Char('t')) => self.pane = Pane::Types,
+            (_, KeyCode::Char('d')) => {
+                self.filter.direction = self.filter.direction.next();
+                self.apply_filter();
+            }
+            (_, KeyCode::Char(c @ '1'..='3')) => {
+                self.filter.depth = (c as u8 - b'0').min(MAX_DEPTH);
+                self.apply_filter();
+            }
+            (_, KeyCode::Char('+' | '=')) => {
+                self.filter.min_confidence = (self.filter.min_confidence + 0.1).min(1.0);
+                self.apply_filter();
+            }
+            (_, KeyCode::Char('-')) => {
+                self.filter.min_confidence = (self.filter.min_confidence - 0.1).max(0.0);
+                self.apply_filter();
+            }
+            (_, KeyCode::Char('x')) => {
+                self.filter = Filter::default();
+                self.apply_filter();
+            }
+
+            (Pane::Concepts, KeyCode::Up) if self.selected > 0 => {
+                self.selected -= 1;
+                self.set_focus(self.selected_name().map(str::to_string));
+            }
+            (Pane::Concepts, KeyCode::Down) if self.selected + 1 < self.concepts.len() => {
+                self.selected += 1;
+                self.set_focus(self.selected_name().map(str::to_string));
+            }
+            (Pane::Concepts, KeyCode::Enter) => {
+                if let Some(name) = self.selected_name().map(str::to_string) {
+                    self.visit(name);
                 }
+                self.pane = Pane::Relations;
             }
-            KeyCode::Char('r') => self.refresh(),
+
+            (Pane::Relations, KeyCode::Up) if self.edge_selected > 0 => self.edge_selected -= 1,
+            (Pane::Relations, KeyCode::Down) if self.edge_selected + 1 < self.edges.len() => {
+                self.edge_selected += 1;
+            }
+            (Pane::Relations, KeyCode::Enter) => self.follow_selected_edge(),
             _ => {}
         }
+        Vec::new()
+    }
+}
+
+fn render_editor(f: &mut Frame, screen: Rect, form: &EditForm) {
+    let area = ui::centered(screen, 70, 11);
+    let mark = |field| if form.field == field { ">" } else { " " };
+    let mut text = format!(
+        "{} Definition: {}\n\n{} Confidence: {}\n\n[Tab] switch field  [Enter] save  [Esc] cancel\n",
+        mark(EditField::Definition),
+        form.definition,
+        mark(EditField::Confidence),
+        form.confidence
+    );
+    if let Some(err) = &form.error {
+        text.push_str(&format!("\n{}", err));
     }
+    let popup = Paragraph::new(text)
+        .wrap(Wrap { trim: false })
+        .block(Block::default().borders(Borders::ALL).title(format!("EDIT {}", form.name)));
+    f.render_widget(Clear, area);
+    f.render_widget(popup, area);
+}
+
+fn pane_title(name: &str, active: bool) -> String {
+    if active { format!("[{}]", name) } else { name.to_string() }
 }
 
-fn render_relations(name: &str, rels: &[Relation]) -> String {
+/// `edges` must be in `load_edges` order; `cursor` indexes into it.
+/// Also returns, per output line, the index of the edge drawn on it.
+fn render_relations(name: &str, edges: &[Edge], cursor: Option<usize>) -> (String, Vec<Option<usize>>) {
     let mut out = String::new();
     out.push_str(&format!("FOCUS: {}\n\n", name));
-    if rels.is_empty() {
-        out.push_str("No relations.\n\nAdd one in DIALOG like:\n  rel jwt uses jws\n  rel jwt used_for authentication\n");
-        return out;
+    if edges.is_empty() {
+        out.push_str("No relations (check FILTER).\n\nAdd one in DIALOG like:\n  rel jwt uses jws\n  rel jwt used_for authentication\n");
+        return (out, Vec::new());
     }
 
-    out.push_str("Outgoing:\n");
-    for r in rels.iter().filter(|r| r.from == name) {
-        out.push_str(&format!("  {} --{}--> {}\n", r.from, r.relation_type, r.to));
-    }
-    out.push('\n');
-    out.push_str("Incoming:\n");
-    for r in rels.iter().filter(|r| r.to == name) {
-        out.push_str(&format!("  {} --{}--> {}\n", r.from, r.relation_type, r.to));
+    let mut lines = vec![None, None];
+
+    let mut section = "";
+    for (i, e) in edges.iter().enumerate() {
+        let is_out = e.rel.from == e.via;
+        let heading = match (e.depth, is_out) {
+            (1, true) => "Outgoing:",
+            (1, false) => "Incoming:",
+            (2, _) => "Depth 2:",
+            _ => "Depth 3:",
+        };
+        if heading != section {
+            if !section.is_empty() {
+                out.push('\n');
+                lines.push(None);
+            }
+            out.push_str(heading);
+            out.push('\n');
+            lines.push(None);
+            section = heading;
+        }
+        let mark = if cursor == Some(i) { ">" } else { " " };
+        out.push_str(&format!("{} {} --{}--> {}\n", mark, e.rel.from, e.rel.relation_type, e.rel.to));
+        lines.push(Some(i));
     }
-    out
+    (out, lines)
 }
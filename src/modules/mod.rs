@@ -1,11 +1,119 @@
-use crossterm::event::KeyEvent;
-use ratatui::Frame;
+use crossterm::event::{KeyCode, KeyEvent, MouseEvent};
+use ratatui::{layout::Rect, Frame};
+
+use crate::bus::{Bus, Message};
+use crate::config::Config;
+use crate::db::Database;
+use crate::keymap::{ctrl, key, Binding, KeyChord};
+use crate::ui::notify::{Level, Notifier};
+
+/// Index into the module registry (`registry`).
+pub type Screen = usize;
+
+/// Requests a module hands back to `App` instead of reaching into other screens.
+#[derive(Clone, PartialEq)]
+pub enum Action {
+    // Registry name, e.g. "graph".
+    Navigate(String),
+    Quit,
+    Notify(Level, String),
+    // Show the concept in whichever screen displays concepts, switching to it.
+    OpenConcept(String),
+    // Every other screen must reopen this database file.
+    Reopen(String),
+    // mother.json was re-read; `App` and every other screen adopt it.
+    ReloadConfig(Config),
+}
 
 pub trait Module {
-    fn render(&mut self, f: &mut Frame);
-    fn handle_input(&mut self, key: KeyEvent);
+    fn render(&mut self, f: &mut Frame, area: Rect);
+    fn handle_input(&mut self, key: KeyEvent) -> Vec<Action>;
+    /// Mouse events carry absolute coordinates; modules hit-test against
+    /// the areas they drew last frame.
+    fn handle_mouse(&mut self, _ev: MouseEvent) -> Vec<Action> {
+        Vec::new()
+    }
+    /// Called about every 100ms while the module is visible.
+    fn tick(&mut self) -> Vec<Action> {
+        Vec::new()
+    }
+    /// The module became visible (selected, or shown in a split pane).
+    fn on_enter(&mut self) {}
+    fn on_leave(&mut self) {}
+    /// Keys this module handles, registered in the central `Keymap`.
+    fn bindings(&self) -> Vec<Binding>;
+    /// True while the user is typing, so plain-char global keys pass through.
+    fn captures_text(&self) -> bool {
+        false
+    }
+    /// A message another module published on the bus; `from` is its registry name.
+    fn on_message(&mut self, _from: &str, _msg: &Message) {}
+    /// Settings re-read from mother.json; the database path is left to `Action::Reopen`.
+    fn reload_config(&mut self, _config: &Config) {}
+    /// Shows `name` if this module displays concepts; false if it doesn't.
+    fn focus_concept(&mut self, _name: &str) -> bool {
+        false
+    }
+}
+
+/// What a constructor gets from `App`.
+pub struct Context<'a> {
+    pub config: &'a Config,
+    pub notifier: &'a Notifier,
+    // Already tagged with the module's name.
+    pub bus: Bus,
+}
+
+/// One screen. Adding an entry to `registry` gives it a tab, a shortcut
+/// and a help section.
+pub struct ModuleSpec {
+    // Lowercase id for `goto` and `Action::Navigate`.
+    pub name: &'static str,
+    pub title: &'static str,
+    pub keys: Vec<KeyChord>,
+    pub build: fn(&Context) -> rusqlite::Result<Box<dyn Module>>,
+}
+
+/// Screens in tab order; the first is shown at startup.
+pub fn registry() -> Vec<ModuleSpec> {
+    vec![
+        ModuleSpec {
+            name: "console",
+            title: "CONSOLE",
+            keys: vec![key(KeyCode::F(4)), ctrl('o')],
+            build: |cx| {
+                let db = Database::init(&cx.config.db_path)?;
+                Ok(Box::new(console::Console::new(db, cx.config.clone(), cx.notifier.clone())))
+            },
+        },
+        ModuleSpec {
+            name: "dialog",
+            title: "DIALOG",
+            keys: vec![key(KeyCode::F(2)), ctrl('d')],
+            build: |cx| {
+                let db = Database::init(&cx.config.db_path)?;
+                Ok(Box::new(dialog::Dialog::new(db, cx.notifier.clone(), cx.bus.clone(), cx.config)))
+            },
+        },
+        ModuleSpec {
+            name: "graph",
+            title: "GRAPH",
+            keys: vec![key(KeyCode::F(3)), ctrl('g')],
+            build: |cx| {
+                let db = Database::init(&cx.config.db_path)?;
+                Ok(Box::new(graph::Graph::new(db, cx.notifier.clone(), cx.bus.clone())))
+            },
+        },
+        ModuleSpec {
+            name: "timeline",
+            title: "TIMELINE",
+            keys: vec![key(KeyCode::F(7)), ctrl('e')],
+            build: |cx| Ok(Box::new(timeline::Timeline::new(Database::init(&cx.config.db_path)?, cx.notifier.clone()))),
+        },
+    ]
 }
 
 pub mod console;
 pub mod dialog;
 pub mod graph;
+pub mod timeline;
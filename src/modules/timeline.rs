@@ -0,0 +1,491 @@
+use std::time::{Duration, Instant};
+
+use crossterm::event::{KeyCode, KeyEvent, MouseButton, MouseEvent, MouseEventKind};
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph, Sparkline, Wrap},
+    Frame,
+};
+use time::{Date, Month, OffsetDateTime};
+
+use super::{Action, Module};
+use crate::bus::Message;
+use crate::db::{Database, Episode};
+use crate::keymap::{ch, key, Binding};
+use crate::ui::{self, notify::Notifier};
+
+const HINTS: &str = "[Enter] open  [o/f/n] outcomes  [r] range  [d] custom range  [?] keys";
+const OUTCOMES: [&str; 3] = ["ok", "fail", "note"];
+const WEEKS: i32 = 26;
+const REFRESH_EVERY: Duration = Duration::from_secs(3);
+
+#[derive(Clone, PartialEq)]
+enum Range {
+    All,
+    LastDays(i64),
+    // Inclusive "YYYY-MM-DD" bounds.
+    Custom(Option<String>, Option<String>),
+}
+
+impl Range {
+    fn next_preset(&self) -> Self {
+        match self {
+            Range::All => Range::LastDays(7),
+            Range::LastDays(7) => Range::LastDays(30),
+            Range::LastDays(30) => Range::LastDays(90),
+            _ => Range::All,
+        }
+    }
+
+    fn bounds(&self) -> (Option<String>, Option<String>) {
+        match self {
+            Range::All => (None, None),
+            Range::LastDays(n) => (Some((today() - time::Duration::days(*n - 1)).to_string()), None),
+            Range::Custom(from, to) => (from.clone(), to.clone()),
+        }
+    }
+
+    fn label(&self) -> String {
+        match self {
+            Range::All => "all time".into(),
+            Range::LastDays(n) => format!("last {} days", n),
+            Range::Custom(from, to) => format!(
+                "{}..{}",
+                from.as_deref().unwrap_or(""),
+                to.as_deref().unwrap_or("")
+            ),
+        }
+    }
+}
+
+pub struct Timeline {
+    db: Database,
+    notifier: Notifier,
+    // Episodes in `range`, before the outcome filter.
+    in_range: Vec<Episode>,
+    // Indices into `in_range` that pass the outcome filter.
+    shown: Vec<usize>,
+    // Ok and fail counts for the sparklines, over the last WEEKS weeks
+    // whatever `range` is.
+    weeks: (Vec<u64>, Vec<u64>),
+    show_outcome: [bool; 3],
+    range: Range,
+    selected: usize,
+    scroll: usize,
+    // Open episode and the concepts its summary names.
+    open: Option<(Episode, Vec<String>)>,
+    // Custom range being typed, "from..to".
+    prompt: Option<String>,
+    refreshed_at: Option<Instant>,
+    list_area: Rect,
+    // Index into `shown` drawn on each list line.
+    lines: Vec<Option<usize>>,
+}
+
+impl Timeline {
+    pub fn new(db: Database, notifier: Notifier) -> Self {
+        Self {
+            db,
+            notifier,
+            in_range: Vec::new(),
+            shown: Vec::new(),
+            weeks: (Vec::new(), Vec::new()),
+            show_outcome: [true; 3],
+            range: Range::All,
+            selected: 0,
+            scroll: 0,
+            open: None,
+            prompt: None,
+            refreshed_at: None,
+            list_area: Rect::default(),
+            lines: Vec::new(),
+        }
+    }
+
+    fn refresh(&mut self) {
+        self.refreshed_at = Some(Instant::now());
+        let keep = self.selected_episode().map(|e| e.id);
+        let (from, to) = self.range.bounds();
+        match self.db.list_episodes_between(from.as_deref(), to.as_deref()) {
+            Ok(eps) => self.in_range = eps,
+            Err(e) => self.notifier.error(format!("TIMELINE: DB error: {}", e)),
+        }
+        let today = today();
+        let start = (today - time::Duration::days(WEEKS as i64 * 7 - 1)).to_string();
+        match self.db.list_episodes_between(Some(&start), None) {
+            Ok(eps) => self.weeks = weekly(&eps, today),
+            Err(e) => self.notifier.error(format!("TIMELINE: DB error: {}", e)),
+        }
+        self.apply_filter(keep);
+    }
+
+    fn apply_filter(&mut self, keep: Option<i64>) {
+        self.shown = self.in_range
+            .iter()
+            .enumerate()
+            .filter(|(_, e)| {
+                OUTCOMES
+                    .iter()
+                    .position(|o| *o == e.outcome)
+                    .is_none_or(|i| self.show_outcome[i])
+            })
+            .map(|(i, _)| i)
+            .collect();
+        self.selected = keep
+            .and_then(|id| self.shown.iter().position(|&i| self.in_range[i].id == id))
+            .unwrap_or(self.shown.len().saturating_sub(1));
+    }
+
+    fn selected_episode(&self) -> Option<&Episode> {
+        self.shown.get(self.selected).map(|&i| &self.in_range[i])
+    }
+
+    fn open_selected(&mut self) {
+        let Some(ep) = self.selected_episode().cloned() else { return };
+        match self.db.concepts_mentioned_in(&ep.summary) {
+            Ok(names) => self.open = Some((ep, names)),
+            Err(e) => self.notifier.error(format!("TIMELINE: DB error: {}", e)),
+        }
+    }
+
+    fn toggle_outcome(&mut self, i: usize) {
+        self.show_outcome[i] = !self.show_outcome[i];
+        self.apply_filter(self.selected_episode().map(|e| e.id));
+    }
+
+    fn set_range(&mut self, range: Range) {
+        self.range = range;
+        self.refresh();
+    }
+
+    fn submit_prompt(&mut self, text: &str) {
+        let (from, to) = text.split_once("..").unwrap_or((text, ""));
+        let parse = |s: &str| -> Result<Option<String>, String> {
+            let s = s.trim();
+            if s.is_empty() {
+                return Ok(None);
+            }
+            parse_day(s)
+                .map(|d| Some(d.to_string()))
+                .ok_or_else(|| format!("'{}' is not a YYYY-MM-DD date", s))
+        };
+        match (parse(from), parse(to)) {
+            (Ok(None), Ok(None)) => self.set_range(Range::All),
+            (Ok(f), Ok(t)) => self.set_range(Range::Custom(f, t)),
+            (Err(e), _) | (_, Err(e)) => self.notifier.warn(format!("TIMELINE: {}", e)),
+        }
+    }
+
+    /// Episodes grouped under one heading per day.
+    fn list_lines(&mut self) -> Vec<Line<'static>> {
+        let mut out = Vec::new();
+        self.lines.clear();
+        let mut day = "";
+        for (n, &i) in self.shown.iter().enumerate() {
+            let e = &self.in_range[i];
+            let this_day = e.captured_at.get(..10).unwrap_or(&e.captured_at);
+            if this_day != day {
+                let count = self.shown[n..]
+                    .iter()
+                    .take_while(|&&j| self.in_range[j].captured_at.starts_with(this_day))
+                    .count();
+                out.push(Line::styled(
+                    format!("── {} ({}) ──", this_day, count),
+                    Style::default().add_modifier(Modifier::BOLD),
+                ));
+                self.lines.push(None);
+                day = this_day;
+            }
+            let mark = if n == self.selected { "> " } else { "  " };
+            let time = e.captured_at.get(11..16).unwrap_or("");
+            out.push(Line::from(vec![
+                Span::raw(format!("{}{}  ", mark, time)),
+                Span::styled(format!("[{:<4}]", e.outcome), Style::default().fg(outcome_color(&e.outcome))),
+                Span::raw(format!(" {}", e.summary)),
+            ]));
+            self.lines.push(Some(n));
+        }
+        if out.is_empty() {
+            out.push(Line::from("No episodes match. Record one in DIALOG: ep ok <what worked>"));
+        }
+        out
+    }
+}
+
+impl Module for Timeline {
+    fn on_enter(&mut self) {
+        self.refresh();
+    }
+
+    fn on_message(&mut self, _from: &str, msg: &Message) {
+        if let Message::EpisodeRecorded { .. } | Message::Reverted(_) = msg {
+            self.refreshed_at = None; // reload on the next tick
+        }
+    }
+
+    fn tick(&mut self) -> Vec<Action> {
+        if self.refreshed_at.is_none_or(|t| t.elapsed() >= REFRESH_EVERY) {
+            self.refresh();
+        }
+        Vec::new()
+    }
+
+    fn render(&mut self, f: &mut Frame, area: Rect) {
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(4), Constraint::Length(4), Constraint::Min(3)])
+            .split(area);
+
+        let filters: Vec<String> = OUTCOMES
+            .iter()
+            .zip(self.show_outcome)
+            .map(|(o, on)| format!("[{}] {}", if on { "x" } else { " " }, o))
+            .collect();
+        let header = Paragraph::new(format!(
+            "{}\nFILTER: {}  range: {}  ({} shown)",
+            HINTS,
+            filters.join(" "),
+            self.range.label(),
+            self.shown.len()
+        ))
+        .block(Block::default().borders(Borders::ALL).title("MOTHER / TIMELINE"));
+        f.render_widget(header, chunks[0]);
+
+        let (ok, fail) = self.weeks.clone();
+        let sparks = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+            .split(chunks[1]);
+        let max = ok.iter().chain(&fail).copied().max().unwrap_or(0).max(1);
+        f.render_widget(
+            Sparkline::default()
+                .data(&ok)
+                .max(max)
+                .style(Style::default().fg(Color::Green))
+                .block(Block::default().borders(Borders::ALL).title(format!("OK / WEEK ({}w)", WEEKS))),
+            sparks[0],
+        );
+        f.render_widget(
+            Sparkline::default()
+                .data(&fail)
+                .max(max)
+                .style(Style::default().fg(Color::Red))
+                .block(Block::default().borders(Borders::ALL).title(format!("FAIL / WEEK ({}w)", WEEKS))),
+            sparks[1],
+        );
+
+        let lines = self.list_lines();
+        let visible = chunks[2].height.saturating_sub(2) as usize;
+        if let Some(line) = self.lines.iter().position(|l| *l == Some(self.selected)) {
+            if line < self.scroll {
+                self.scroll = line.saturating_sub(1); // keep the day heading in view
+            } else if line >= self.scroll + visible {
+                self.scroll = line + 1 - visible;
+            }
+        }
+        self.scroll = self.scroll.min(lines.len().saturating_sub(visible));
+        let list = Paragraph::new(lines)
+            .scroll((self.scroll as u16, 0))
+            .block(Block::default().borders(Borders::ALL).title("EPISODES"));
+        f.render_widget(list, chunks[2]);
+        self.list_area = chunks[2];
+
+        if let Some((ep, concepts)) = &self.open {
+            render_episode(f, area, ep, concepts);
+        }
+        if let Some(text) = &self.prompt {
+            let rect = ui::centered(area, 60, 3);
+            let popup = Paragraph::new(format!("> {}", text))
+                .block(Block::default().borders(Borders::ALL).title("RANGE  YYYY-MM-DD..YYYY-MM-DD  [Enter] apply  [Esc] cancel"));
+            f.render_widget(Clear, rect);
+            f.render_widget(popup, rect);
+        }
+    }
+
+    fn handle_input(&mut self, key: KeyEvent) -> Vec<Action> {
+        if let Some(text) = self.prompt.as_mut() {
+            match key.code {
+                KeyCode::Esc => self.prompt = None,
+                KeyCode::Enter => {
+                    let text = self.prompt.take().unwrap_or_default();
+                    self.submit_prompt(&text);
+                }
+                KeyCode::Backspace => {
+                    text.pop();
+                }
+                KeyCode::Char(c) => text.push(c),
+                _ => {}
+            }
+            return Vec::new();
+        }
+        if let Some((_, concepts)) = &self.open {
+            match key.code {
+                KeyCode::Esc | KeyCode::Enter | KeyCode::Char('q') => self.open = None,
+                // Digits pick a linked concept to view in GRAPH.
+                KeyCode::Char(c @ '1'..='9') => {
+                    if let Some(name) = concepts.get(c as usize - '1' as usize) {
+                        return vec![Action::OpenConcept(name.clone())];
+                    }
+                }
+                _ => {}
+            }
+            return Vec::new();
+        }
+
+        match key.code {
+            KeyCode::Up if self.selected > 0 => self.selected -= 1,
+            KeyCode::Down if self.selected + 1 < self.shown.len() => self.selected += 1,
+            KeyCode::Home => self.selected = 0,
+            KeyCode::End => self.selected = self.shown.len().saturating_sub(1),
+            KeyCode::Enter => self.open_selected(),
+            KeyCode::Char('o') => self.toggle_outcome(0),
+            KeyCode::Char('f') => self.toggle_outcome(1),
+            KeyCode::Char('n') => self.toggle_outcome(2),
+            KeyCode::Char('r') => self.set_range(self.range.next_preset()),
+            KeyCode::Char('d') => {
+                let (from, to) = self.range.bounds();
+                self.prompt = Some(format!("{}..{}", from.unwrap_or_default(), to.unwrap_or_default()));
+            }
+            _ => {}
+        }
+        Vec::new()
+    }
+
+    fn handle_mouse(&mut self, ev: MouseEvent) -> Vec<Action> {
+        if self.open.is_some() || self.prompt.is_some() || !ui::contains(self.list_area, ev.column, ev.row) {
+            return Vec::new();
+        }
+        match ev.kind {
+            MouseEventKind::ScrollUp => return self.handle_input(KeyEvent::from(KeyCode::Up)),
+            MouseEventKind::ScrollDown => return self.handle_input(KeyEvent::from(KeyCode::Down)),
+            MouseEventKind::Down(MouseButton::Left) => {
+                let line = (ev.row - self.list_area.y) as usize;
+                if line == 0 {
+                    return Vec::new();
+                }
+                if let Some(Some(n)) = self.lines.get(self.scroll + line - 1).copied() {
+                    // A click on the selected episode opens it.
+                    if n == self.selected {
+                        self.open_selected();
+                    }
+                    self.selected = n;
+                }
+            }
+            _ => {}
+        }
+        Vec::new()
+    }
+
+    fn bindings(&self) -> Vec<Binding> {
+        vec![
+            Binding::new(&[key(KeyCode::Up), key(KeyCode::Down)], "select episode"),
+            Binding::new(&[key(KeyCode::Home), key(KeyCode::End)], "oldest / newest"),
+            Binding::new(&[key(KeyCode::Enter)], "open episode (Esc closes)"),
+            Binding::new(&[ch('1'), ch('9')], "in an open episode: view linked concept 1..9 in GRAPH"),
+            Binding::new(&[ch('o'), ch('f'), ch('n')], "show / hide ok, fail, note"),
+            Binding::new(&[ch('r')], "cycle range: all, 7, 30, 90 days"),
+            Binding::new(&[ch('d')], "custom date range"),
+        ]
+    }
+
+    fn captures_text(&self) -> bool {
+        self.prompt.is_some()
+    }
+}
+
+fn render_episode(f: &mut Frame, screen: Rect, ep: &Episode, concepts: &[String]) {
+    let area = ui::centered(screen, 70, 16);
+    let mut lines = vec![
+        Line::from(vec![
+            Span::raw(format!("#{}  {}  ", ep.id, ep.captured_at)),
+            Span::styled(ep.outcome.to_uppercase(), Style::default().fg(outcome_color(&ep.outcome))),
+        ]),
+        Line::from(""),
+        Line::from(ep.summary.clone()),
+        Line::from(""),
+        Line::from("Linked concepts:"),
+    ];
+    if concepts.is_empty() {
+        lines.push(Line::from("  (none mentioned)"));
+    }
+    lines.extend(concepts.iter().enumerate().map(|(i, c)| {
+        let key = if i < 9 { format!("[{}]", i + 1) } else { " - ".into() };
+        Line::from(format!("  {} {}", key, c))
+    }));
+    let popup = Paragraph::new(lines)
+        .wrap(Wrap { trim: false })
+        .block(Block::default().borders(Borders::ALL).title("EPISODE  [1-9] open in GRAPH  [Esc] close"));
+    f.render_widget(Clear, area);
+    f.render_widget(popup, area);
+}
+
+fn outcome_color(outcome: &str) -> Color {
+    match outcome {
+        "ok" => Color::Green,
+        "fail" => Color::Red,
+        _ => Color::Yellow,
+    }
+}
+
+/// Ok and fail counts per week, oldest first, ending with `today`'s week.
+fn weekly(episodes: &[Episode], today: Date) -> (Vec<u64>, Vec<u64>) {
+    let mut ok = vec![0; WEEKS as usize];
+    let mut fail = vec![0; WEEKS as usize];
+    let today = today.to_julian_day();
+    for e in episodes {
+        let Some(day) = parse_day(&e.captured_at) else { continue };
+        let weeks_ago = (today - day.to_julian_day()) / 7;
+        if !(0..WEEKS).contains(&weeks_ago) {
+            continue;
+        }
+        let slot = (WEEKS - 1 - weeks_ago) as usize;
+        match e.outcome.as_str() {
+            "ok" => ok[slot] += 1,
+            "fail" => fail[slot] += 1,
+            _ => {}
+        }
+    }
+    (ok, fail)
+}
+
+fn today() -> Date {
+    OffsetDateTime::now_utc().date()
+}
+
+/// Reads the leading "YYYY-MM-DD" of a stored timestamp or user input.
+fn parse_day(s: &str) -> Option<Date> {
+    let year = s.get(0..4)?.parse().ok()?;
+    let month: u8 = s.get(5..7)?.parse().ok()?;
+    let day = s.get(8..10)?.parse().ok()?;
+    if s.get(4..5)? != "-" || s.get(7..8)? != "-" {
+        return None;
+    }
+    Date::from_calendar_date(year, Month::try_from(month).ok()?, day).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::Scratch;
+
+    #[test]
+    fn sparklines_ignore_the_range() {
+        let file = Scratch::new("timeline.db");
+        let db = Database::init(&file.string()).unwrap();
+        let month_ago = format!("{}T09:00:00Z", today() - time::Duration::days(30));
+        rusqlite::Connection::open(file.path())
+            .unwrap()
+            .execute("INSERT INTO episodes (captured_at, outcome, summary) VALUES (?1, 'ok', 'old')", [&month_ago])
+            .unwrap();
+        db.add_episode("fail", "recent").unwrap();
+
+        let mut timeline = Timeline::new(db, Notifier::default());
+        timeline.set_range(Range::LastDays(7));
+        assert_eq!(timeline.in_range.len(), 1);
+        let (ok, fail) = &timeline.weeks;
+        assert_eq!((ok.iter().sum::<u64>(), fail.iter().sum::<u64>()), (1, 1));
+        assert_eq!(fail[WEEKS as usize - 1], 1);
+    }
+}
@@ -0,0 +1,225 @@
+// Markdown notes with `[[wiki-links]]`, as read by `import notes`.
+//
+// A note's concept is its front-matter `title`, else its first `# heading`,
+// else its file name; the definition is the first paragraph of the body.
+// Body links become "links to" relations; front-matter keys whose values
+// hold links become typed relations (`part_of: [[jose]]` -> "part of").
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Relation type for wiki-links in the body.
+pub const BODY_LINK: &str = "links to";
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Note {
+    pub path: PathBuf,
+    // Lowercased, like every concept name.
+    pub name: String,
+    // Empty when the note has no paragraph of text.
+    pub definition: String,
+    // (relation type, target concept), deduplicated, self-links dropped.
+    pub links: Vec<(String, String)>,
+    // FNV-1a of the file, to skip unchanged notes on the next import.
+    pub hash: String,
+}
+
+/// Every `.md` file under `dir`, sorted by path, with link targets resolved:
+/// a link naming another note's file (`[[jwt]]` for jwt.md) points at that
+/// note's concept.
+pub fn scan(dir: &Path) -> Result<Vec<Note>, String> {
+    let mut files = Vec::new();
+    walk(dir, &mut files)?;
+    files.sort();
+
+    let mut notes = Vec::new();
+    for path in files {
+        let text = fs::read_to_string(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        notes.push(parse(&path, &text));
+    }
+
+    let by_stem: HashMap<String, String> =
+        notes.iter().map(|n| (stem(&n.path).to_lowercase(), n.name.clone())).collect();
+    for note in &mut notes {
+        let mut links = Vec::new();
+        for (kind, target) in note.links.drain(..) {
+            let target = by_stem.get(&target).cloned().unwrap_or(target);
+            if target != note.name && !links.contains(&(kind.clone(), target.clone())) {
+                links.push((kind, target));
+            }
+        }
+        note.links = links;
+    }
+    Ok(notes)
+}
+
+fn walk(dir: &Path, out: &mut Vec<PathBuf>) -> Result<(), String> {
+    let entries = fs::read_dir(dir).map_err(|e| format!("{}: {}", dir.display(), e))?;
+    for entry in entries {
+        let entry = entry.map_err(|e| format!("{}: {}", dir.display(), e))?;
+        let path = entry.path();
+        let hidden = path.file_name().and_then(|n| n.to_str()).is_some_and(|n| n.starts_with('.'));
+        if hidden {
+            continue;
+        }
+        let kind = entry.file_type().map_err(|e| format!("{}: {}", path.display(), e))?;
+        if kind.is_dir() {
+            walk(&path, out)?;
+        } else if kind.is_symlink() && path.is_dir() {
+            // Not followed: a link to a parent would recurse forever.
+            continue;
+        } else if path.extension().is_some_and(|e| e.eq_ignore_ascii_case("md")) {
+            out.push(path);
+        }
+    }
+    Ok(())
+}
+
+fn stem(path: &Path) -> &str {
+    path.file_stem().and_then(|s| s.to_str()).unwrap_or("")
+}
+
+/// One note; link targets are left unresolved (see `scan`).
+pub fn parse(path: &Path, text: &str) -> Note {
+    let (front, body) = split_front_matter(text);
+
+    let mut title = None;
+    let mut links = Vec::new();
+    // Key whose value continues on indented `- item` lines.
+    let mut list_key: Option<String> = None;
+    for line in front.lines() {
+        let trimmed = line.trim();
+        if let Some(item) = trimmed.strip_prefix("- ").filter(|_| line.starts_with([' ', '\t', '-'])) {
+            if let Some(key) = &list_key {
+                links.extend(wiki_links(item).into_iter().map(|t| (relation_name(key), t)));
+            }
+            continue;
+        }
+        let Some((key, value)) = trimmed.split_once(':') else { continue };
+        let (key, value) = (key.trim().to_lowercase(), unquote(value.trim()));
+        list_key = value.is_empty().then(|| key.clone());
+        if key == "title" && !value.is_empty() {
+            title = Some(value.to_string());
+        } else {
+            links.extend(wiki_links(value).into_iter().map(|t| (relation_name(&key), t)));
+        }
+    }
+
+    let mut paragraph: Vec<&str> = Vec::new();
+    for line in body.lines() {
+        let line = line.trim();
+        if let Some(heading) = line.strip_prefix("# ") {
+            if title.is_none() {
+                title = Some(heading.trim().to_string());
+            }
+            if paragraph.is_empty() {
+                continue;
+            }
+        }
+        if line.starts_with('#') || line.is_empty() {
+            if paragraph.is_empty() {
+                continue;
+            }
+            break;
+        }
+        paragraph.push(line);
+    }
+    links.extend(wiki_links(body).into_iter().map(|t| (BODY_LINK.to_string(), t)));
+
+    let name = title.unwrap_or_else(|| stem(path).to_string()).trim().to_lowercase();
+    Note {
+        path: path.to_path_buf(),
+        name,
+        definition: link_text(&paragraph.join(" ")),
+        links,
+        hash: fnv1a(text.as_bytes()),
+    }
+}
+
+fn split_front_matter(text: &str) -> (&str, &str) {
+    let Some(rest) = text.strip_prefix("---\n").or_else(|| text.strip_prefix("---\r\n")) else {
+        return ("", text);
+    };
+    let mut at = 0;
+    for line in rest.split_inclusive('\n') {
+        if line.trim_end() == "---" {
+            return (&rest[..at], &rest[at + line.len()..]);
+        }
+        at += line.len();
+    }
+    ("", text)
+}
+
+fn unquote(value: &str) -> &str {
+    value.trim_matches(|c| c == '"' || c == '\'')
+}
+
+/// `part_of` and `part-of` become "part of".
+fn relation_name(key: &str) -> String {
+    key.replace(['_', '-'], " ")
+}
+
+/// Targets of the `[[target|alias]]` / `[[folder/target#heading]]` links in
+/// `text`, lowercased.
+fn wiki_links(text: &str) -> Vec<String> {
+    let mut out = Vec::new();
+    let mut rest = text;
+    while let Some(start) = rest.find("[[") {
+        let Some(len) = rest[start + 2..].find("]]") else { break };
+        let inner = &rest[start + 2..start + 2 + len];
+        let target = inner.split(['|', '#']).next().unwrap_or("");
+        let target = target.rsplit('/').next().unwrap_or("").trim().to_lowercase();
+        if !target.is_empty() {
+            out.push(target);
+        }
+        rest = &rest[start + 2 + len + 2..];
+    }
+    out
+}
+
+/// `text` with each link replaced by what a reader sees: its alias, or its target.
+fn link_text(text: &str) -> String {
+    let mut out = String::new();
+    let mut rest = text;
+    while let Some(start) = rest.find("[[") {
+        let Some(len) = rest[start + 2..].find("]]") else { break };
+        let inner = &rest[start + 2..start + 2 + len];
+        out.push_str(&rest[..start]);
+        out.push_str(inner.rsplit_once('|').map_or(inner.split('#').next().unwrap_or(""), |(_, alias)| alias));
+        rest = &rest[start + 2 + len + 2..];
+    }
+    out.push_str(rest);
+    out.trim().to_string()
+}
+
+// Stable across Rust releases, unlike `DefaultHasher`.
+fn fnv1a(bytes: &[u8]) -> String {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for &b in bytes {
+        hash ^= u64::from(b);
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    format!("{:016x}", hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::Scratch;
+
+    #[cfg(unix)]
+    #[test]
+    fn symlinked_directories_are_not_followed() {
+        let dir = Scratch::dir("notes-links");
+        fs::create_dir_all(dir.join("sub")).unwrap();
+        fs::write(dir.join("sub/jwt.md"), "# jwt\n\nA signed token.\n").unwrap();
+        std::os::unix::fs::symlink(dir.path(), dir.join("sub/up")).unwrap();
+        std::os::unix::fs::symlink(dir.join("sub/jwt.md"), dir.join("alias.md")).unwrap();
+
+        let notes = scan(dir.path()).unwrap();
+        let paths: Vec<_> = notes.into_iter().map(|n| n.path.strip_prefix(dir.path()).unwrap().to_path_buf()).collect();
+        assert_eq!(paths, [PathBuf::from("alias.md"), PathBuf::from("sub/jwt.md")]);
+    }
+}
@@ -0,0 +1,863 @@
+// RDF interchange: Turtle and JSON-LD. Every IRI hangs off a base IRI
+// (`rdf_base` in mother.json):
+//
+//   <base>concept/<name>      a concept, with rdfs:label (its name),
+//                             rdfs:comment (its definition) and
+//                             <base>vocab#confidence
+//   <base>relation/<type>     predicate of a relation between two concepts
+//
+// Names are percent-encoded into IRIs. The readers take the parts of each
+// syntax other tools commonly write; RDF collections, and JSON-LD contexts
+// loaded from a URL, are not supported. Triples that don't fit the mapping
+// are ignored.
+use std::collections::{BTreeMap, HashMap};
+
+use serde_json::{json, Map, Value};
+
+use crate::db::Database;
+use crate::export::Subgraph;
+
+pub const DEFAULT_BASE: &str = "urn:mother:";
+
+const RDF_TYPE: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#type";
+const RDFS: &str = "http://www.w3.org/2000/01/rdf-schema#";
+const XSD: &str = "http://www.w3.org/2001/XMLSchema#";
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RdfFormat {
+    Turtle,
+    JsonLd,
+}
+
+impl RdfFormat {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "turtle" | "ttl" => Some(Self::Turtle),
+            "jsonld" | "json-ld" | "json" => Some(Self::JsonLd),
+            _ => None,
+        }
+    }
+
+    /// Guessed from the file extension: .ttl, .jsonld/.json.
+    pub fn from_path(path: &str) -> Option<Self> {
+        Self::from_name(path.rsplit_once('.')?.1)
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Turtle => "turtle",
+            Self::JsonLd => "jsonld",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ConceptRecord {
+    pub name: String,
+    pub definition: String,
+    // None when an imported resource has no confidence.
+    pub confidence: Option<f64>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Link {
+    pub from: String,
+    pub relation_type: String,
+    pub to: String,
+}
+
+/// What gets written or read: concepts sorted by name, links by endpoints.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Knowledge {
+    pub concepts: Vec<ConceptRecord>,
+    pub links: Vec<Link>,
+}
+
+/// The concepts (with definitions) and relations of an `export::collect` graph.
+pub fn collect(db: &Database, graph: &Subgraph) -> rusqlite::Result<Knowledge> {
+    let mut concepts = Vec::new();
+    for (name, confidence) in &graph.nodes {
+        if confidence.is_none() {
+            continue;
+        }
+        if let Some(c) = db.get_concept(name)? {
+            concepts.push(ConceptRecord { name: c.name, definition: c.definition, confidence: Some(c.confidence) });
+        }
+    }
+    let links = graph
+        .edges
+        .iter()
+        .map(|r| Link { from: r.from.clone(), relation_type: r.relation_type.clone(), to: r.to.clone() })
+        .collect();
+    Ok(Knowledge { concepts, links })
+}
+
+/// IRIs under one base.
+struct Names<'a> {
+    base: &'a str,
+}
+
+impl Names<'_> {
+    fn concepts(&self) -> String {
+        format!("{}concept/", self.base)
+    }
+
+    fn relations(&self) -> String {
+        format!("{}relation/", self.base)
+    }
+
+    fn vocab(&self) -> String {
+        format!("{}vocab#", self.base)
+    }
+
+    fn confidence(&self) -> String {
+        format!("{}confidence", self.vocab())
+    }
+}
+
+pub fn render(knowledge: &Knowledge, base: &str, format: RdfFormat) -> String {
+    let names = Names { base };
+    match format {
+        RdfFormat::Turtle => turtle(knowledge, &names),
+        RdfFormat::JsonLd => jsonld(knowledge, &names),
+    }
+}
+
+pub fn parse(text: &str, base: &str, format: RdfFormat) -> Result<Knowledge, String> {
+    let triples = match format {
+        RdfFormat::Turtle => TurtleParser::new(text)?.triples()?,
+        RdfFormat::JsonLd => jsonld_triples(text)?,
+    };
+    Ok(knowledge(triples, &Names { base }))
+}
+
+// ---- writing ----
+
+/// Links grouped by their `from` concept.
+fn links_by_source(knowledge: &Knowledge) -> BTreeMap<&str, Vec<&Link>> {
+    let mut out: BTreeMap<&str, Vec<&Link>> = BTreeMap::new();
+    for link in &knowledge.links {
+        out.entry(link.from.as_str()).or_default().push(link);
+    }
+    out
+}
+
+fn turtle(knowledge: &Knowledge, names: &Names) -> String {
+    // Encoded names only hold [A-Za-z0-9_%-], which prefixed names allow
+    // anywhere but at the start ('-') or when empty.
+    let term = |prefix: &str, ns: &str, name: &str| {
+        let local = encode(name);
+        if local.is_empty() || local.starts_with('-') {
+            format!("<{}{}>", ns, local)
+        } else {
+            format!("{}:{}", prefix, local)
+        }
+    };
+    let (concepts, relations) = (names.concepts(), names.relations());
+    let concept = |name: &str| term("c", &concepts, name);
+    let relation = |name: &str| term("r", &relations, name);
+
+    let mut out = format!(
+        "@prefix rdfs: <{}> .\n@prefix xsd: <{}> .\n@prefix mo: <{}> .\n@prefix c: <{}> .\n@prefix r: <{}> .\n",
+        RDFS,
+        XSD,
+        names.vocab(),
+        concepts,
+        relations,
+    );
+    let mut links = links_by_source(knowledge);
+    for c in &knowledge.concepts {
+        out.push_str(&format!("\n{} a mo:Concept ;\n", concept(&c.name)));
+        let mut lines = vec![format!("rdfs:label {}", turtle_string(&c.name)), format!("rdfs:comment {}", turtle_string(&c.definition))];
+        if let Some(confidence) = c.confidence {
+            lines.push(format!("mo:confidence \"{}\"^^xsd:decimal", confidence));
+        }
+        for link in links.remove(c.name.as_str()).unwrap_or_default() {
+            lines.push(format!("{} {}", relation(&link.relation_type), concept(&link.to)));
+        }
+        out.push_str(&format!("    {} .\n", lines.join(" ;\n    ")));
+    }
+    // Relations from names with no concept record.
+    for (from, group) in links {
+        let lines: Vec<String> = group.iter().map(|l| format!("{} {}", relation(&l.relation_type), concept(&l.to))).collect();
+        out.push_str(&format!("\n{} {} .\n", concept(from), lines.join(" ;\n    ")));
+    }
+    out
+}
+
+fn turtle_string(text: &str) -> String {
+    let mut out = String::from("\"");
+    for ch in text.chars() {
+        match ch {
+            '\\' => out.push_str("\\\\"),
+            '"' => out.push_str("\\\""),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+fn jsonld(knowledge: &Knowledge, names: &Names) -> String {
+    let id = |name: &str| format!("c:{}", encode(name));
+    let relation = |name: &str| format!("r:{}", encode(name));
+
+    let mut links = links_by_source(knowledge);
+    let mut nodes = Vec::new();
+    let mut add_links = |node: &mut Map<String, Value>, from: &str| {
+        for link in links.remove(from).unwrap_or_default() {
+            let targets = node.entry(relation(&link.relation_type)).or_insert_with(|| json!([]));
+            if let Value::Array(targets) = targets {
+                targets.push(json!({ "@id": id(&link.to) }));
+            }
+        }
+    };
+    for c in &knowledge.concepts {
+        let mut node = Map::new();
+        node.insert("@id".into(), json!(id(&c.name)));
+        node.insert("@type".into(), json!("mo:Concept"));
+        node.insert("label".into(), json!(c.name));
+        node.insert("comment".into(), json!(c.definition));
+        if let Some(confidence) = c.confidence {
+            node.insert("confidence".into(), json!(confidence.to_string()));
+        }
+        add_links(&mut node, &c.name);
+        nodes.push(Value::Object(node));
+    }
+    let dangling: Vec<String> = links_by_source(knowledge)
+        .into_keys()
+        .filter(|from| !knowledge.concepts.iter().any(|c| c.name == *from))
+        .map(str::to_string)
+        .collect();
+    for from in dangling {
+        let mut node = Map::new();
+        node.insert("@id".into(), json!(id(&from)));
+        add_links(&mut node, &from);
+        nodes.push(Value::Object(node));
+    }
+
+    let doc = json!({
+        "@context": {
+            "rdfs": RDFS,
+            "xsd": XSD,
+            "mo": names.vocab(),
+            "c": names.concepts(),
+            "r": names.relations(),
+            "label": "rdfs:label",
+            "comment": "rdfs:comment",
+            "confidence": { "@id": "mo:confidence", "@type": "xsd:decimal" },
+        },
+        "@graph": nodes,
+    });
+    let mut out = serde_json::to_string_pretty(&doc).expect("JSON values always serialize");
+    out.push('\n');
+    out
+}
+
+/// Percent-encoding of everything but ASCII letters, digits, '_' and '-'.
+fn encode(name: &str) -> String {
+    let mut out = String::new();
+    for b in name.bytes() {
+        if b.is_ascii_alphanumeric() || b == b'_' || b == b'-' {
+            out.push(b as char);
+        } else {
+            out.push_str(&format!("%{:02X}", b));
+        }
+    }
+    out
+}
+
+fn decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes.get(i + 1..i + 3).and_then(|h| std::str::from_utf8(h).ok());
+        match (bytes[i], hex.and_then(|h| u8::from_str_radix(h, 16).ok())) {
+            (b'%', Some(b)) => {
+                out.push(b);
+                i += 3;
+            }
+            (b, _) => {
+                out.push(b);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+// ---- reading ----
+
+#[derive(Clone, Debug, PartialEq)]
+enum Object {
+    Iri(String),
+    // Lexical form; language tags and datatypes are dropped.
+    Literal(String),
+}
+
+struct Triple {
+    subject: String,
+    predicate: String,
+    object: Object,
+}
+
+fn knowledge(triples: Vec<Triple>, names: &Names) -> Knowledge {
+    let (concepts_ns, relations_ns, confidence) = (names.concepts(), names.relations(), names.confidence());
+    let (label, comment) = (format!("{}label", RDFS), format!("{}comment", RDFS));
+
+    let mut labels: HashMap<String, String> = HashMap::new();
+    let mut comments: BTreeMap<String, String> = BTreeMap::new();
+    let mut confidences: HashMap<String, f64> = HashMap::new();
+    let mut raw_links = Vec::new();
+    for t in triples {
+        match t.object {
+            Object::Literal(v) if t.predicate == label => {
+                labels.entry(t.subject).or_insert(v);
+            }
+            Object::Literal(v) if t.predicate == comment => {
+                comments.entry(t.subject).or_insert(v);
+            }
+            Object::Literal(v) if t.predicate == confidence => {
+                if let Some(c) = v.trim().parse::<f64>().ok().filter(|c| (0.0..=1.0).contains(c)) {
+                    confidences.insert(t.subject, c);
+                }
+            }
+            Object::Iri(o) => {
+                if let Some(kind) = t.predicate.strip_prefix(&relations_ns) {
+                    raw_links.push((t.subject, decode(kind), o));
+                }
+            }
+            Object::Literal(_) => {}
+        }
+    }
+
+    // The label, else the IRI's last segment.
+    let name_of = |iri: &str| {
+        let name = match labels.get(iri) {
+            Some(label) => label.clone(),
+            None => {
+                let local = iri.strip_prefix(&concepts_ns).unwrap_or_else(|| {
+                    iri.rsplit(['/', '#', ':']).find(|s| !s.is_empty()).unwrap_or(iri)
+                });
+                decode(local)
+            }
+        };
+        name.trim().to_lowercase()
+    };
+
+    let mut concepts: Vec<ConceptRecord> = comments
+        .iter()
+        .map(|(iri, definition)| ConceptRecord {
+            name: name_of(iri),
+            definition: definition.trim().to_string(),
+            confidence: confidences.get(iri).copied(),
+        })
+        .filter(|c| !c.name.is_empty() && !c.definition.is_empty())
+        .collect();
+    concepts.sort_by(|a, b| a.name.cmp(&b.name));
+    concepts.dedup_by(|a, b| a.name == b.name);
+
+    let mut links: Vec<Link> = raw_links
+        .iter()
+        .map(|(s, kind, o)| Link { from: name_of(s), relation_type: kind.trim().to_lowercase(), to: name_of(o) })
+        .filter(|l| !l.from.is_empty() && !l.to.is_empty() && !l.relation_type.is_empty() && l.from != l.to)
+        .collect();
+    links.sort();
+    links.dedup();
+    Knowledge { concepts, links }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Tok {
+    Iri(String),
+    // Prefixed names, blank node labels, numbers, `a`, `true`, PREFIX...
+    Word(String),
+    Str(String),
+    // `@prefix`, `@base` or a language tag, without the '@'.
+    At(String),
+    Carets,
+    Punct(char),
+}
+
+struct TurtleParser {
+    toks: Vec<(Tok, usize)>,
+    at: usize,
+    prefixes: HashMap<String, String>,
+    base: String,
+    blanks: usize,
+    out: Vec<Triple>,
+}
+
+impl TurtleParser {
+    fn new(text: &str) -> Result<Self, String> {
+        Ok(Self { toks: lex(text)?, at: 0, prefixes: HashMap::new(), base: String::new(), blanks: 0, out: Vec::new() })
+    }
+
+    fn triples(mut self) -> Result<Vec<Triple>, String> {
+        while self.at < self.toks.len() {
+            self.statement()?;
+        }
+        Ok(self.out)
+    }
+
+    fn line(&self) -> usize {
+        self.toks.get(self.at).or(self.toks.last()).map_or(1, |(_, line)| *line)
+    }
+
+    fn fail<T>(&self, message: impl Into<String>) -> Result<T, String> {
+        Err(format!("line {}: {}", self.line(), message.into()))
+    }
+
+    fn next(&mut self) -> Result<Tok, String> {
+        match self.toks.get(self.at) {
+            Some((tok, _)) => {
+                self.at += 1;
+                Ok(tok.clone())
+            }
+            None => self.fail("unexpected end of file"),
+        }
+    }
+
+    fn peek(&self) -> Option<&Tok> {
+        self.toks.get(self.at).map(|(tok, _)| tok)
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), String> {
+        match self.next()? {
+            Tok::Punct(p) if p == c => Ok(()),
+            other => {
+                self.at -= 1;
+                self.fail(format!("expected '{}', found {}", c, describe(&other)))
+            }
+        }
+    }
+
+    fn statement(&mut self) -> Result<(), String> {
+        match self.next()? {
+            Tok::At(d) if d == "prefix" => {
+                self.prefix()?;
+                self.expect('.')
+            }
+            Tok::At(d) if d == "base" => {
+                self.base = self.iri()?;
+                self.expect('.')
+            }
+            Tok::Word(w) if w.eq_ignore_ascii_case("prefix") => self.prefix(),
+            Tok::Word(w) if w.eq_ignore_ascii_case("base") => {
+                self.base = self.iri()?;
+                Ok(())
+            }
+            _ => {
+                self.at -= 1;
+                let subject = self.node()?;
+                if subject.1 && self.peek() == Some(&Tok::Punct('.')) {
+                    // `[ ... ] .` on its own.
+                } else {
+                    self.predicate_objects(&subject.0)?;
+                }
+                self.expect('.')
+            }
+        }
+    }
+
+    fn prefix(&mut self) -> Result<(), String> {
+        let name = match self.next()? {
+            Tok::Word(w) if w.ends_with(':') => w[..w.len() - 1].to_string(),
+            other => return self.fail(format!("expected a prefix like 'ex:', found {}", describe(&other))),
+        };
+        let iri = self.iri()?;
+        self.prefixes.insert(name, iri);
+        Ok(())
+    }
+
+    /// An `<iri>` or prefixed name.
+    fn iri(&mut self) -> Result<String, String> {
+        match self.next()? {
+            Tok::Iri(iri) if iri.contains(':') => Ok(iri),
+            Tok::Iri(relative) => Ok(format!("{}{}", self.base, relative)),
+            Tok::Word(w) if w.contains(':') && !w.starts_with("_:") => {
+                let (prefix, local) = w.split_once(':').unwrap_or_default();
+                match self.prefixes.get(prefix) {
+                    Some(ns) => Ok(format!("{}{}", ns, local)),
+                    None => self.fail(format!("undefined prefix '{}:'", prefix)),
+                }
+            }
+            other => self.fail(format!("expected an IRI, found {}", describe(&other))),
+        }
+    }
+
+    /// A subject or object resource; true for a `[ ... ]` blank node.
+    fn node(&mut self) -> Result<(String, bool), String> {
+        match self.peek() {
+            Some(Tok::Word(w)) if w.starts_with("_:") => {
+                let w = w.clone();
+                self.at += 1;
+                Ok((w, false))
+            }
+            Some(Tok::Punct('[')) => {
+                self.at += 1;
+                self.blanks += 1;
+                let id = format!("_:b{}", self.blanks);
+                if self.peek() != Some(&Tok::Punct(']')) {
+                    self.predicate_objects(&id)?;
+                }
+                self.expect(']')?;
+                Ok((id, true))
+            }
+            Some(Tok::Punct('(')) => self.fail("RDF collections are not supported"),
+            _ => Ok((self.iri()?, false)),
+        }
+    }
+
+    fn predicate_objects(&mut self, subject: &str) -> Result<(), String> {
+        loop {
+            let predicate = match self.peek() {
+                Some(Tok::Word(w)) if w == "a" => {
+                    self.at += 1;
+                    RDF_TYPE.to_string()
+                }
+                _ => self.iri()?,
+            };
+            loop {
+                let object = self.object()?;
+                self.out.push(Triple { subject: subject.to_string(), predicate: predicate.clone(), object });
+                if self.peek() != Some(&Tok::Punct(',')) {
+                    break;
+                }
+                self.at += 1;
+            }
+            if self.peek() != Some(&Tok::Punct(';')) {
+                return Ok(());
+            }
+            while self.peek() == Some(&Tok::Punct(';')) {
+                self.at += 1;
+            }
+            if matches!(self.peek(), Some(Tok::Punct('.' | ']'))) {
+                return Ok(());
+            }
+        }
+    }
+
+    fn object(&mut self) -> Result<Object, String> {
+        match self.peek().cloned() {
+            Some(Tok::Str(s)) => {
+                self.at += 1;
+                match self.peek() {
+                    Some(Tok::At(_)) => self.at += 1,
+                    Some(Tok::Carets) => {
+                        self.at += 1;
+                        self.iri()?;
+                    }
+                    _ => {}
+                }
+                Ok(Object::Literal(s))
+            }
+            Some(Tok::Word(w)) if w == "true" || w == "false" || w.starts_with(|c: char| c.is_ascii_digit() || "+-.".contains(c)) => {
+                self.at += 1;
+                Ok(Object::Literal(w))
+            }
+            _ => Ok(Object::Iri(self.node()?.0)),
+        }
+    }
+}
+
+fn describe(tok: &Tok) -> String {
+    match tok {
+        Tok::Iri(iri) => format!("<{}>", iri),
+        Tok::Word(w) => format!("'{}'", w),
+        Tok::Str(_) => "a string".into(),
+        Tok::At(a) => format!("'@{}'", a),
+        Tok::Carets => "'^^'".into(),
+        Tok::Punct(c) => format!("'{}'", c),
+    }
+}
+
+fn lex(text: &str) -> Result<Vec<(Tok, usize)>, String> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut toks = Vec::new();
+    let mut line = 1;
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        match c {
+            '\n' => {
+                line += 1;
+                i += 1;
+            }
+            c if c.is_whitespace() => i += 1,
+            '#' => {
+                while i < chars.len() && chars[i] != '\n' {
+                    i += 1;
+                }
+            }
+            '<' => {
+                let start = i + 1;
+                let Some(len) = chars[start..].iter().position(|&c| c == '>') else {
+                    return Err(format!("line {}: unterminated IRI", line));
+                };
+                toks.push((Tok::Iri(chars[start..start + len].iter().collect()), line));
+                i = start + len + 1;
+            }
+            '"' | '\'' => {
+                let start_line = line;
+                let long = chars.get(i + 1) == Some(&c) && chars.get(i + 2) == Some(&c);
+                i += if long { 3 } else { 1 };
+                let mut s = String::new();
+                loop {
+                    let Some(&ch) = chars.get(i) else {
+                        return Err(format!("line {}: unterminated string", start_line));
+                    };
+                    if ch == c && (!long || (chars.get(i + 1) == Some(&c) && chars.get(i + 2) == Some(&c))) {
+                        i += if long { 3 } else { 1 };
+                        break;
+                    }
+                    if ch == '\n' {
+                        if !long {
+                            return Err(format!("line {}: unterminated string", start_line));
+                        }
+                        line += 1;
+                    }
+                    if ch == '\\' {
+                        let (unescaped, len) = unescape(&chars[i + 1..]).ok_or_else(|| format!("line {}: bad escape in string", line))?;
+                        s.push(unescaped);
+                        i += 1 + len;
+                    } else {
+                        s.push(ch);
+                        i += 1;
+                    }
+                }
+                toks.push((Tok::Str(s), start_line));
+            }
+            '@' => {
+                let start = i + 1;
+                i = start;
+                while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '-') {
+                    i += 1;
+                }
+                toks.push((Tok::At(chars[start..i].iter().collect()), line));
+            }
+            '^' if chars.get(i + 1) == Some(&'^') => {
+                toks.push((Tok::Carets, line));
+                i += 2;
+            }
+            ',' | ';' | '[' | ']' | '(' | ')' => {
+                toks.push((Tok::Punct(c), line));
+                i += 1;
+            }
+            _ => {
+                let mut word = String::new();
+                while let Some(&ch) = chars.get(i) {
+                    if ch.is_whitespace() || "<>\"',;[]()#^".contains(ch) {
+                        break;
+                    }
+                    // `\-` and friends escape characters in local names.
+                    if ch == '\\' && i + 1 < chars.len() {
+                        word.push(chars[i + 1]);
+                        i += 2;
+                        continue;
+                    }
+                    word.push(ch);
+                    i += 1;
+                }
+                // A trailing '.' ends the statement rather than the name.
+                let dots = word.len() - word.trim_end_matches('.').len();
+                word.truncate(word.len() - dots);
+                if word.is_empty() && dots == 0 {
+                    return Err(format!("line {}: unexpected '{}'", line, c));
+                }
+                if !word.is_empty() {
+                    toks.push((Tok::Word(word), line));
+                }
+                toks.extend((0..dots).map(|_| (Tok::Punct('.'), line)));
+            }
+        }
+    }
+    Ok(toks)
+}
+
+/// The character for the escape after a '\' and how many chars it used.
+fn unescape(rest: &[char]) -> Option<(char, usize)> {
+    let hex = |n: usize| {
+        let digits: String = rest.get(1..=n)?.iter().collect();
+        char::from_u32(u32::from_str_radix(&digits, 16).ok()?).map(|c| (c, n + 1))
+    };
+    match rest.first()? {
+        't' => Some(('\t', 1)),
+        'n' => Some(('\n', 1)),
+        'r' => Some(('\r', 1)),
+        'b' => Some(('\u{8}', 1)),
+        'f' => Some(('\u{c}', 1)),
+        'u' => hex(4),
+        'U' => hex(8),
+        c @ ('"' | '\'' | '\\') => Some((*c, 1)),
+        _ => None,
+    }
+}
+
+/// A JSON-LD term: what it expands to and whether its string values are IRIs.
+struct TermDef {
+    id: String,
+    iri_values: bool,
+}
+
+#[derive(Default)]
+struct Context {
+    terms: HashMap<String, TermDef>,
+    vocab: Option<String>,
+    base: Option<String>,
+}
+
+impl Context {
+    fn read(value: &Value) -> Result<Self, String> {
+        let mut ctx = Context::default();
+        let parts = match value {
+            Value::Array(parts) => parts.iter().collect(),
+            Value::Null => Vec::new(),
+            other => vec![other],
+        };
+        for part in parts {
+            let Value::Object(map) = part else {
+                return Err("only inline @context objects are supported".into());
+            };
+            for (key, def) in map {
+                match (key.as_str(), def) {
+                    ("@vocab", Value::String(v)) => ctx.vocab = Some(v.clone()),
+                    ("@base", Value::String(b)) => ctx.base = Some(b.clone()),
+                    (_, Value::String(id)) => {
+                        ctx.terms.insert(key.clone(), TermDef { id: id.clone(), iri_values: false });
+                    }
+                    (_, Value::Object(def)) => {
+                        let id = def.get("@id").and_then(Value::as_str).unwrap_or(key);
+                        let iri_values = matches!(def.get("@type").and_then(Value::as_str), Some("@id" | "@vocab"));
+                        ctx.terms.insert(key.clone(), TermDef { id: id.to_string(), iri_values });
+                    }
+                    _ => {}
+                }
+            }
+        }
+        Ok(ctx)
+    }
+
+    /// `value` as an absolute IRI: a term, a compact IRI, or relative to
+    /// @vocab (for properties and types) or @base (for ids).
+    fn expand(&self, value: &str, vocab: bool) -> String {
+        self.expand_depth(value, vocab, 0)
+    }
+
+    fn expand_depth(&self, value: &str, vocab: bool, depth: usize) -> String {
+        if depth > 8 {
+            return value.to_string();
+        }
+        if vocab && let Some(term) = self.terms.get(value) {
+            return self.expand_depth(&term.id, true, depth + 1);
+        }
+        if let Some((prefix, rest)) = value.split_once(':') {
+            if prefix == "_" || rest.starts_with("//") {
+                return value.to_string();
+            }
+            return match self.terms.get(prefix) {
+                Some(term) => format!("{}{}", self.expand_depth(&term.id, true, depth + 1), rest),
+                None => value.to_string(),
+            };
+        }
+        match (vocab, &self.vocab, &self.base) {
+            (true, Some(v), _) => format!("{}{}", v, value),
+            (false, _, Some(b)) => format!("{}{}", b, value),
+            _ => value.to_string(),
+        }
+    }
+}
+
+fn jsonld_triples(text: &str) -> Result<Vec<Triple>, String> {
+    let doc: Value = serde_json::from_str(text).map_err(|e| format!("bad JSON: {}", e))?;
+    let (ctx, nodes) = match &doc {
+        Value::Object(map) => {
+            let ctx = Context::read(map.get("@context").unwrap_or(&Value::Null))?;
+            match map.get("@graph") {
+                Some(Value::Array(nodes)) => (ctx, nodes.iter().collect()),
+                Some(_) => return Err("@graph must be an array".into()),
+                None => (ctx, vec![&doc]),
+            }
+        }
+        Value::Array(nodes) => (Context::default(), nodes.iter().collect()),
+        _ => return Err("expected a JSON-LD object or array".into()),
+    };
+    let mut reader = JsonLdReader { ctx, blanks: 0, out: Vec::new() };
+    for node in nodes {
+        if let Value::Object(node) = node {
+            reader.node(node);
+        }
+    }
+    Ok(reader.out)
+}
+
+struct JsonLdReader {
+    ctx: Context,
+    blanks: usize,
+    out: Vec<Triple>,
+}
+
+impl JsonLdReader {
+    /// Emits the node's triples and returns its IRI.
+    fn node(&mut self, node: &Map<String, Value>) -> String {
+        let subject = match node.get("@id").and_then(Value::as_str) {
+            Some(id) => self.ctx.expand(id, false),
+            None => {
+                self.blanks += 1;
+                format!("_:b{}", self.blanks)
+            }
+        };
+        for (key, value) in node {
+            if key == "@type" {
+                let types = match value {
+                    Value::Array(types) => types.iter().filter_map(Value::as_str).collect(),
+                    other => other.as_str().into_iter().collect::<Vec<_>>(),
+                };
+                for t in types {
+                    let object = Object::Iri(self.ctx.expand(t, true));
+                    self.out.push(Triple { subject: subject.clone(), predicate: RDF_TYPE.into(), object });
+                }
+                continue;
+            }
+            if key.starts_with('@') {
+                continue;
+            }
+            let predicate = self.ctx.expand(key, true);
+            // Keys that expand to no IRI are dropped, as JSON-LD does.
+            if !predicate.contains(':') {
+                continue;
+            }
+            let iri_values = self.ctx.terms.get(key).is_some_and(|t| t.iri_values);
+            let values = match value {
+                Value::Array(values) => values.iter().collect(),
+                other => vec![other],
+            };
+            for value in values {
+                if let Some(object) = self.value(value, iri_values) {
+                    self.out.push(Triple { subject: subject.clone(), predicate: predicate.clone(), object });
+                }
+            }
+        }
+        subject
+    }
+
+    fn value(&mut self, value: &Value, iri_values: bool) -> Option<Object> {
+        match value {
+            Value::String(s) if iri_values => Some(Object::Iri(self.ctx.expand(s, false))),
+            Value::String(s) => Some(Object::Literal(s.clone())),
+            Value::Number(n) => Some(Object::Literal(n.to_string())),
+            Value::Bool(b) => Some(Object::Literal(b.to_string())),
+            Value::Object(map) => match map.get("@value") {
+                Some(Value::String(s)) => Some(Object::Literal(s.clone())),
+                Some(Value::Null) | None if map.contains_key("@list") || map.contains_key("@set") => None,
+                Some(v) => Some(Object::Literal(v.to_string())),
+                None => Some(Object::Iri(self.node(map))),
+            },
+            _ => None,
+        }
+    }
+}
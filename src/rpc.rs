@@ -0,0 +1,341 @@
+// `mother-terminal rpc`: the `api` operations as line-delimited JSON-RPC 2.0
+// on a Unix socket next to the database (`mother.db.sock`). One request per
+// line in, one response per line out; requests without an id get no reply.
+//
+// Clients that call `subscribe` also get a `changed` notification for every
+// write to the database, from this server or any other process, read from
+// the audit log.
+//
+//   concepts {limit?}   concept {name}         relations {concept, limit?}
+//   episodes {limit?}   search {q, limit?}     path {from, to, max?}
+//   audit {concept?, operator?, limit?}
+//   learn {name, definition, confidence?}      proposal   confirm   reject
+//   rel {from, type, to}   ep {outcome, summary}   undo   redo
+//   dialog {line}       any DIALOG command line, e.g. {"line": "show jwt"}
+//   subscribe   unsubscribe
+use std::{
+    fs,
+    io::{ErrorKind as IoErrorKind, Read, Write},
+    os::unix::{
+        fs::{FileTypeExt, PermissionsExt},
+        net::{UnixListener, UnixStream},
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use serde::{de::DeserializeOwned, Deserialize};
+use serde_json::{json, Value};
+
+use mother_terminal::api::{Api, ApiError, ErrorKind, NewConcept, NewEpisode, NewRelation, DEFAULT_MAX_HOPS};
+use mother_terminal::config::Config;
+
+// Sleep between polls of the socket when nothing happened.
+const IDLE: Duration = Duration::from_millis(20);
+// How often the audit log is checked for new writes.
+const CHANGE_POLL: Duration = Duration::from_millis(500);
+// A client that can't take a line within this is dropped.
+const WRITE_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Optional fields shared by the read methods' params.
+#[derive(Default, Deserialize)]
+#[serde(default)]
+struct Params {
+    limit: Option<usize>,
+    name: Option<String>,
+    concept: Option<String>,
+    operator: Option<String>,
+    q: Option<String>,
+    from: Option<String>,
+    to: Option<String>,
+    max: Option<usize>,
+    line: Option<String>,
+}
+
+enum Failure {
+    Method(String),
+    Params(String),
+    Api(ApiError),
+}
+
+impl From<ApiError> for Failure {
+    fn from(e: ApiError) -> Self {
+        Failure::Api(e)
+    }
+}
+
+struct Client {
+    stream: UnixStream,
+    // Bytes read but not yet ended by a newline.
+    buf: Vec<u8>,
+    subscribed: bool,
+    closed: bool,
+}
+
+impl Client {
+    fn new(stream: UnixStream) -> Self {
+        Self { stream, buf: Vec::new(), subscribed: false, closed: false }
+    }
+
+    /// Complete lines received since the last call.
+    fn read_lines(&mut self) -> Vec<String> {
+        let mut chunk = [0u8; 4096];
+        loop {
+            match self.stream.read(&mut chunk) {
+                Ok(0) => {
+                    self.closed = true;
+                    break;
+                }
+                Ok(n) => self.buf.extend_from_slice(&chunk[..n]),
+                Err(e) if e.kind() == IoErrorKind::WouldBlock => break,
+                Err(e) if e.kind() == IoErrorKind::Interrupted => continue,
+                Err(_) => {
+                    self.closed = true;
+                    break;
+                }
+            }
+        }
+        let mut lines = Vec::new();
+        while let Some(end) = self.buf.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = self.buf.drain(..=end).collect();
+            let line = String::from_utf8_lossy(&line).trim().to_string();
+            if !line.is_empty() {
+                lines.push(line);
+            }
+        }
+        lines
+    }
+
+    fn send(&mut self, message: &Value) {
+        let mut line = message.to_string();
+        line.push('\n');
+        let sent = self
+            .stream
+            .set_nonblocking(false)
+            .and_then(|()| self.stream.write_all(line.as_bytes()))
+            .and_then(|()| self.stream.set_nonblocking(true));
+        if sent.is_err() {
+            self.closed = true;
+        }
+    }
+}
+
+pub fn socket_path(db_path: &str) -> String {
+    format!("{}.sock", db_path)
+}
+
+/// Serves until the process is killed.
+pub fn serve(config: &Config) -> Result<(), String> {
+    let path = socket_path(&config.db_path);
+    if let Ok(meta) = fs::symlink_metadata(&path) {
+        if !meta.file_type().is_socket() {
+            return Err(format!("{} exists and is not a socket; not touching it", path));
+        }
+        match UnixStream::connect(&path) {
+            Ok(_) => return Err(format!("{} is already being served", path)),
+            // Left behind by a server that was killed.
+            Err(e) if e.kind() == IoErrorKind::ConnectionRefused => {}
+            Err(e) => return Err(format!("cannot check {}: {}", path, e)),
+        }
+        fs::remove_file(&path).map_err(|e| format!("cannot remove stale {}: {}", path, e))?;
+    }
+    let listener = bind_private(&path)?;
+    listener.set_nonblocking(true).map_err(|e| format!("{}: {}", path, e))?;
+
+    let mut api = Api::open(config)?;
+    let mut seen = api.db().latest_audit_id().map_err(|e| format!("cannot read audit log: {}", e))?;
+    let mut polled = Instant::now();
+    let mut clients: Vec<Client> = Vec::new();
+    eprintln!("MOTHER: JSON-RPC for {} on {}", config.db_path, path);
+
+    loop {
+        let mut idle = true;
+        loop {
+            match listener.accept() {
+                Ok((stream, _)) => {
+                    if stream.set_nonblocking(true).and_then(|()| stream.set_write_timeout(Some(WRITE_TIMEOUT))).is_ok() {
+                        clients.push(Client::new(stream));
+                    }
+                }
+                Err(e) if e.kind() == IoErrorKind::WouldBlock => break,
+                Err(e) => {
+                    eprintln!("MOTHER: accept failed: {}", e);
+                    break;
+                }
+            }
+        }
+
+        for client in clients.iter_mut() {
+            for line in client.read_lines() {
+                idle = false;
+                if let Some(response) = handle(&mut api, client, &line) {
+                    client.send(&response);
+                }
+            }
+        }
+
+        if polled.elapsed() >= CHANGE_POLL {
+            polled = Instant::now();
+            match api.db().audit_after(seen, 100) {
+                Ok(entries) => {
+                    for entry in entries {
+                        seen = entry.id;
+                        let note = json!({ "jsonrpc": "2.0", "method": "changed", "params": entry });
+                        for client in clients.iter_mut().filter(|c| c.subscribed) {
+                            client.send(&note);
+                        }
+                    }
+                }
+                Err(e) => eprintln!("MOTHER: cannot read audit log: {}", e),
+            }
+        }
+
+        clients.retain(|c| !c.closed);
+        if idle {
+            thread::sleep(IDLE);
+        }
+    }
+}
+
+/// A socket at `path` that only this user can connect to. It is bound under
+/// a temporary name and renamed once it is 0600, so it is never reachable
+/// with the umask's mode.
+fn bind_private(path: &str) -> Result<UnixListener, String> {
+    let tmp = format!("{}.{}.tmp", path, std::process::id());
+    let _ = fs::remove_file(&tmp);
+    let listener = UnixListener::bind(&tmp).map_err(|e| format!("cannot listen on {}: {}", path, e))?;
+    let private = fs::set_permissions(&tmp, fs::Permissions::from_mode(0o600)).and_then(|()| fs::rename(&tmp, path));
+    if let Err(e) = private {
+        let _ = fs::remove_file(&tmp);
+        return Err(format!("cannot set up {}: {}", path, e));
+    }
+    Ok(listener)
+}
+
+/// The response to one request line, or None for a notification.
+fn handle(api: &mut Api, client: &mut Client, line: &str) -> Option<Value> {
+    let request: Value = match serde_json::from_str(line) {
+        Ok(v) => v,
+        Err(e) => return Some(error(Value::Null, -32700, &format!("parse error: {}", e), None)),
+    };
+    let id = request.get("id").cloned();
+    let Some(method) = request.get("method").and_then(Value::as_str) else {
+        return Some(error(id.unwrap_or(Value::Null), -32600, "invalid request: missing method", None));
+    };
+    let params = request.get("params").cloned().unwrap_or_else(|| json!({}));
+    let result = call(api, client, method, params);
+
+    let id = id?;
+    Some(match result {
+        Ok(v) => json!({ "jsonrpc": "2.0", "id": id, "result": v }),
+        Err(Failure::Method(m)) => error(id, -32601, &format!("no method '{}'", m), None),
+        Err(Failure::Params(m)) => error(id, -32602, &m, None),
+        Err(Failure::Api(e)) => {
+            let code = match e.kind {
+                ErrorKind::Invalid => -32000,
+                ErrorKind::NotFound => -32004,
+                ErrorKind::Conflict => -32009,
+                ErrorKind::Internal => -32603,
+            };
+            let data = (!e.transcript.is_empty()).then(|| json!({ "transcript": e.transcript }));
+            error(id, code, &e.error, data)
+        }
+    })
+}
+
+fn call(api: &mut Api, client: &mut Client, method: &str, params: Value) -> Result<Value, Failure> {
+    Ok(match method {
+        "concepts" => api.concepts(read::<Params>(params)?.limit.unwrap_or(20))?,
+        "concept" => api.concept(&required(read::<Params>(params)?.name, "name")?)?,
+        "relations" => {
+            let p: Params = read(params)?;
+            api.relations(&required(p.concept, "concept")?, p.limit.unwrap_or(200))?
+        }
+        "episodes" => api.episodes(read::<Params>(params)?.limit.unwrap_or(20))?,
+        "search" => {
+            let p: Params = read(params)?;
+            api.search(&required(p.q, "q")?, p.limit.unwrap_or(20))?
+        }
+        "path" => {
+            let p: Params = read(params)?;
+            api.path(&required(p.from, "from")?, &required(p.to, "to")?, p.max.unwrap_or(DEFAULT_MAX_HOPS))?
+        }
+        "audit" => {
+            let p: Params = read(params)?;
+            api.audit(p.concept.as_deref(), p.operator.as_deref(), p.limit.unwrap_or(20))?
+        }
+        "learn" => api.learn(&read::<NewConcept>(params)?)?,
+        "proposal" => api.pending(),
+        "confirm" => api.decide(true)?,
+        "reject" => api.decide(false)?,
+        "rel" => api.relate(&read::<NewRelation>(params)?)?,
+        "ep" => api.record_episode(&read::<NewEpisode>(params)?)?,
+        "undo" | "redo" => api.run(method)?,
+        "dialog" => api.run(&required(read::<Params>(params)?.line, "line")?)?,
+        "subscribe" | "unsubscribe" => {
+            client.subscribed = method == "subscribe";
+            json!({ "subscribed": client.subscribed })
+        }
+        other => return Err(Failure::Method(other.to_string())),
+    })
+}
+
+fn read<T: DeserializeOwned>(params: Value) -> Result<T, Failure> {
+    serde_json::from_value(params).map_err(|e| Failure::Params(format!("invalid params: {}", e)))
+}
+
+fn required(value: Option<String>, name: &str) -> Result<String, Failure> {
+    value.ok_or_else(|| Failure::Params(format!("missing param '{}'", name)))
+}
+
+fn error(id: Value, code: i64, message: &str, data: Option<Value>) -> Value {
+    let mut err = json!({ "code": code, "message": message });
+    if let Some(data) = data {
+        err["data"] = data;
+    }
+    json!({ "jsonrpc": "2.0", "id": id, "error": err })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::Scratch;
+
+    // The database and its socket, inside a scratch directory.
+    fn paths(dir: &Scratch) -> (String, String) {
+        let db = dir.join("mother.db").to_string_lossy().into_owned();
+        let sock = socket_path(&db);
+        (db, sock)
+    }
+
+    #[test]
+    fn socket_is_private() {
+        let dir = Scratch::dir("rpc-private");
+        let (_, path) = paths(&dir);
+        let _listener = bind_private(&path).unwrap();
+        let meta = fs::symlink_metadata(&path).unwrap();
+        assert!(meta.file_type().is_socket());
+        assert_eq!(meta.permissions().mode() & 0o777, 0o600);
+        assert!(UnixStream::connect(&path).is_ok());
+    }
+
+    #[test]
+    fn leaves_other_files_alone() {
+        let dir = Scratch::dir("rpc-other");
+        let (db, sock) = paths(&dir);
+        fs::write(&sock, "not a socket").unwrap();
+        let config = Config { db_path: db, ..Config::default() };
+        let err = serve(&config).unwrap_err();
+        assert!(err.contains("is not a socket"), "{}", err);
+        assert_eq!(fs::read_to_string(&sock).unwrap(), "not a socket");
+    }
+
+    #[test]
+    fn refuses_a_live_socket() {
+        let dir = Scratch::dir("rpc-live");
+        let (db, sock) = paths(&dir);
+        let _listener = UnixListener::bind(&sock).unwrap();
+        let config = Config { db_path: db, ..Config::default() };
+        assert!(serve(&config).unwrap_err().contains("already being served"));
+    }
+}
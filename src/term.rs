@@ -0,0 +1,103 @@
+// Terminal lifecycle: setup/teardown that also runs on panics, signals and Ctrl+Z.
+use std::{
+    io::{self, Write},
+    panic,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+use crossterm::{
+    event::{DisableMouseCapture, EnableMouseCapture},
+    execute,
+    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+};
+
+pub fn enter() -> io::Result<()> {
+    enable_raw_mode()?;
+    execute!(io::stdout(), EnterAlternateScreen, EnableMouseCapture)
+}
+
+/// Best effort: every step runs even if an earlier one fails.
+pub fn leave() -> io::Result<()> {
+    let raw = disable_raw_mode();
+    let screen = execute!(io::stdout(), DisableMouseCapture, LeaveAlternateScreen);
+    let _ = io::stdout().flush();
+    raw.and(screen)
+}
+
+/// Restores the terminal before the default hook prints the panic message,
+/// so it lands on the user's normal screen instead of the alternate one.
+pub fn install_panic_hook() {
+    let default = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        let _ = leave();
+        default(info);
+    }));
+}
+
+/// Flags raised by signal handlers and polled by the app loop between frames,
+/// so shutdown runs through the normal path and drops every connection.
+pub struct Signals {
+    quit: Arc<AtomicBool>,
+    suspend: Arc<AtomicBool>,
+    resumed: Arc<AtomicBool>,
+}
+
+impl Signals {
+    #[cfg(unix)]
+    pub fn register() -> io::Result<Self> {
+        use signal_hook::{consts::*, flag};
+
+        let s = Self {
+            quit: Arc::new(AtomicBool::new(false)),
+            suspend: Arc::new(AtomicBool::new(false)),
+            resumed: Arc::new(AtomicBool::new(false)),
+        };
+        for sig in [SIGTERM, SIGHUP, SIGINT] {
+            flag::register(sig, Arc::clone(&s.quit))?;
+        }
+        flag::register(SIGTSTP, Arc::clone(&s.suspend))?;
+        flag::register(SIGCONT, Arc::clone(&s.resumed))?;
+        Ok(s)
+    }
+
+    #[cfg(not(unix))]
+    pub fn register() -> io::Result<Self> {
+        Ok(Self {
+            quit: Arc::new(AtomicBool::new(false)),
+            suspend: Arc::new(AtomicBool::new(false)),
+            resumed: Arc::new(AtomicBool::new(false)),
+        })
+    }
+
+    pub fn quit_requested(&self) -> bool {
+        self.quit.load(Ordering::Relaxed)
+    }
+
+    /// True once per external SIGTSTP (e.g. `kill -TSTP`).
+    pub fn take_suspend(&self) -> bool {
+        self.suspend.swap(false, Ordering::Relaxed)
+    }
+
+    /// True once after the process was continued; the screen needs a full redraw.
+    pub fn take_resumed(&self) -> bool {
+        self.resumed.swap(false, Ordering::Relaxed)
+    }
+}
+
+/// Hands the terminal back to the shell and stops the process like an
+/// ordinary Ctrl+Z; returns after `fg` with the terminal set up again.
+#[cfg(unix)]
+pub fn suspend() -> io::Result<()> {
+    leave()?;
+    // Our SIGTSTP handler only sets a flag, so stop the process explicitly.
+    signal_hook::low_level::emulate_default_handler(signal_hook::consts::SIGTSTP)?;
+    enter()
+}
+
+#[cfg(not(unix))]
+pub fn suspend() -> io::Result<()> {
+    Ok(())
+}
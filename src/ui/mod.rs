@@ -1,2 +1,66 @@
-// UI helpers will live here in v1.
-// v0 keeps layout inside modules.
+// Shared UI helpers; screen layout still lives inside modules.
+pub mod notify;
+
+use ratatui::{
+    layout::Rect,
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph},
+    Frame,
+};
+
+/// A `width`% wide, `height` rows tall rectangle centered in `area`.
+pub fn centered(area: Rect, width: u16, height: u16) -> Rect {
+    let w = area.width * width / 100;
+    let h = height.min(area.height);
+    Rect {
+        x: area.x + (area.width - w) / 2,
+        y: area.y + (area.height - h) / 2,
+        width: w,
+        height: h,
+    }
+}
+
+/// Key binding overlay drawn over the current screen.
+pub fn render_help(f: &mut Frame, lines: &[String]) {
+    let area = centered(f.area(), 70, lines.len() as u16 + 4);
+    let popup = Paragraph::new(lines.join("\n"))
+        .block(Block::default().borders(Borders::ALL).title("KEYS  [Esc] close"));
+    f.render_widget(Clear, area);
+    f.render_widget(popup, area);
+}
+
+/// One-line screen tab bar; returns each tab's clickable area.
+/// `active` is highlighted, other `visible` tabs (split panes) underlined.
+pub fn render_tabs<T: Copy + PartialEq>(
+    f: &mut Frame,
+    area: Rect,
+    tabs: &[(T, &str)],
+    active: T,
+    visible: &[T],
+) -> Vec<(Rect, T)> {
+    let mut spans = Vec::new();
+    let mut hits = Vec::new();
+    let mut x = area.x;
+    for (id, title) in tabs {
+        let label = format!(" {} ", title);
+        let width = label.chars().count() as u16;
+        let style = if *id == active {
+            Style::default().add_modifier(Modifier::REVERSED | Modifier::BOLD)
+        } else if visible.contains(id) {
+            Style::default().add_modifier(Modifier::UNDERLINED)
+        } else {
+            Style::default()
+        };
+        hits.push((Rect { x, y: area.y, width, height: 1 }, *id));
+        spans.push(Span::styled(label, style));
+        spans.push(Span::raw(" "));
+        x += width + 1;
+    }
+    f.render_widget(Paragraph::new(Line::from(spans)), area);
+    hits
+}
+
+pub fn contains(r: Rect, column: u16, row: u16) -> bool {
+    column >= r.x && column < r.x + r.width && row >= r.y && row < r.y + r.height
+}
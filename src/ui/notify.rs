@@ -0,0 +1,167 @@
+use std::{
+    cell::RefCell,
+    collections::VecDeque,
+    rc::Rc,
+    time::{Duration, Instant},
+};
+
+use ratatui::{
+    layout::Rect,
+    style::{Color, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph, Wrap},
+    Frame,
+};
+use time::OffsetDateTime;
+
+const HISTORY: usize = 200;
+const MAX_TOASTS: usize = 3;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Level {
+    Info,
+    Warning,
+    Error,
+}
+
+impl Level {
+    fn ttl(self) -> Duration {
+        match self {
+            Level::Info => Duration::from_secs(4),
+            Level::Warning => Duration::from_secs(6),
+            Level::Error => Duration::from_secs(10),
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Level::Info => "INFO",
+            Level::Warning => "WARN",
+            Level::Error => "ERROR",
+        }
+    }
+
+    fn color(self) -> Color {
+        match self {
+            Level::Info => Color::Green,
+            Level::Warning => Color::Yellow,
+            Level::Error => Color::Red,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct Notice {
+    pub level: Level,
+    pub text: String,
+    // Wall-clock "HH:MM:SS" (UTC) for the history view.
+    pub stamp: String,
+    raised: Instant,
+}
+
+impl Notice {
+    fn expired(&self) -> bool {
+        self.raised.elapsed() >= self.level.ttl()
+    }
+}
+
+/// Cheap-to-clone handle every module holds to raise messages that the app
+/// shows in the status bar, as toasts and in the history overlay.
+#[derive(Clone, Default)]
+pub struct Notifier(Rc<RefCell<VecDeque<Notice>>>);
+
+impl Notifier {
+    pub fn notify(&self, level: Level, text: impl Into<String>) {
+        let now = OffsetDateTime::now_utc();
+        let mut log = self.0.borrow_mut();
+        log.push_back(Notice {
+            level,
+            text: text.into(),
+            stamp: format!("{:02}:{:02}:{:02}", now.hour(), now.minute(), now.second()),
+            raised: Instant::now(),
+        });
+        if log.len() > HISTORY {
+            log.pop_front();
+        }
+    }
+
+    pub fn info(&self, text: impl Into<String>) {
+        self.notify(Level::Info, text);
+    }
+
+    pub fn warn(&self, text: impl Into<String>) {
+        self.notify(Level::Warning, text);
+    }
+
+    pub fn error(&self, text: impl Into<String>) {
+        self.notify(Level::Error, text);
+    }
+
+    /// Unexpired notices, newest first.
+    pub fn active(&self) -> Vec<Notice> {
+        self.0.borrow().iter().rev().filter(|n| !n.expired()).cloned().collect()
+    }
+
+    /// Every retained notice, newest first.
+    pub fn history(&self) -> Vec<Notice> {
+        self.0.borrow().iter().rev().cloned().collect()
+    }
+}
+
+fn styled(n: &Notice) -> Line<'static> {
+    Line::from(vec![
+        Span::styled(format!("{:<5} ", n.level.label()), Style::default().fg(n.level.color())),
+        Span::raw(n.text.clone()),
+    ])
+}
+
+/// Bottom line: the newest active notice, or `hint` when all is quiet.
+pub fn render_status_bar(f: &mut Frame, area: Rect, notifier: &Notifier, hint: &str) {
+    let line = match notifier.active().first() {
+        Some(n) => styled(n),
+        None => Line::from(hint.to_string()),
+    };
+    f.render_widget(Paragraph::new(line), area);
+}
+
+/// Stacks unexpired notices in the top-right corner of `area`.
+pub fn render_toasts(f: &mut Frame, area: Rect, notifier: &Notifier) {
+    let width = (area.width / 3).max(30).min(area.width);
+    let mut y = area.y;
+    for n in notifier.active().iter().take(MAX_TOASTS) {
+        if y + 3 > area.y + area.height {
+            break;
+        }
+        let rect = Rect { x: area.x + area.width - width, y, width, height: 3 };
+        let toast = Paragraph::new(n.text.clone()).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(n.level.color()))
+                .title(n.level.label()),
+        );
+        f.render_widget(Clear, rect);
+        f.render_widget(toast, rect);
+        y += 3;
+    }
+}
+
+pub fn render_history(f: &mut Frame, notifier: &Notifier) {
+    let area = super::centered(f.area(), 80, f.area().height.saturating_sub(4));
+    let mut lines: Vec<Line> = notifier
+        .history()
+        .iter()
+        .map(|n| {
+            let mut l = styled(n);
+            l.spans.insert(0, Span::raw(format!("{}  ", n.stamp)));
+            l
+        })
+        .collect();
+    if lines.is_empty() {
+        lines.push(Line::from("No notifications yet."));
+    }
+    let popup = Paragraph::new(lines)
+        .wrap(Wrap { trim: false })
+        .block(Block::default().borders(Borders::ALL).title("NOTIFICATIONS  [Esc] close"));
+    f.render_widget(Clear, area);
+    f.render_widget(popup, area);
+}
@@ -0,0 +1,68 @@
+// Scratch files and directories for tests. Integration tests use this as
+// `mod common;`; the library's and binary's unit tests include it with
+// `#[path]`, so there is one copy of the temp-file-and-Drop dance.
+#![allow(dead_code)]
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process,
+};
+
+/// `mother-<pid>-<name>` under the temp dir, cleared of anything an earlier
+/// run left there and removed again on drop, whether a file or a directory.
+pub struct Scratch(PathBuf);
+
+impl Scratch {
+    /// Nothing is created; the path is free for the test to use.
+    pub fn new(name: &str) -> Self {
+        let path = std::env::temp_dir().join(format!("mother-{}-{}", process::id(), name));
+        remove(&path);
+        Self(path)
+    }
+
+    /// A file holding `text`.
+    pub fn file(name: &str, text: &str) -> Self {
+        let scratch = Self::new(name);
+        fs::write(&scratch.0, text).unwrap();
+        scratch
+    }
+
+    /// An empty directory; everything put in it goes with it.
+    pub fn dir(name: &str) -> Self {
+        let scratch = Self::new(name);
+        fs::create_dir_all(&scratch.0).unwrap();
+        scratch
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.0
+    }
+
+    /// The path as a string, for the many APIs that take `&str`.
+    pub fn string(&self) -> String {
+        self.0.to_string_lossy().into_owned()
+    }
+
+    pub fn join(&self, name: &str) -> PathBuf {
+        self.0.join(name)
+    }
+}
+
+impl Drop for Scratch {
+    fn drop(&mut self) {
+        remove(&self.0);
+    }
+}
+
+fn remove(path: &Path) {
+    match fs::symlink_metadata(path) {
+        Ok(meta) if meta.is_dir() => {
+            let _ = fs::remove_dir_all(path);
+        }
+        Ok(_) => {
+            let _ = fs::remove_file(path);
+        }
+        Err(_) => {}
+    }
+}
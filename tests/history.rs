@@ -0,0 +1,107 @@
+// Undo/redo is scoped to the writing session: another process sharing the
+// file (simulated by rewriting rows directly) is never reverted or overwritten.
+mod common;
+
+use common::Scratch;
+use mother_terminal::Database;
+use rusqlite::Connection;
+
+/// Hands every undo_log row so far to another session.
+fn give_away(file: &Scratch) {
+    let conn = Connection::open(file.path()).unwrap();
+    conn.execute("UPDATE undo_log SET session = 'someone-else', operator = 'ripley'", []).unwrap();
+}
+
+fn definition(db: &Database, name: &str) -> Option<String> {
+    db.get_concept(name).unwrap().map(|c| c.definition)
+}
+
+#[test]
+fn undo_skips_other_sessions() {
+    let file = Scratch::new("history-skip.db");
+    let db = Database::init(&file.string()).unwrap();
+    db.upsert_concept("jwt", "theirs", 0.5).unwrap();
+    give_away(&file);
+
+    assert_eq!(db.undo().unwrap(), None);
+    assert_eq!(definition(&db, "jwt").as_deref(), Some("theirs"));
+
+    db.upsert_concept("jws", "mine", 0.5).unwrap();
+    assert!(db.undo().unwrap().is_some());
+    assert_eq!(definition(&db, "jws"), None);
+    assert_eq!(db.undo().unwrap(), None);
+    assert_eq!(definition(&db, "jwt").as_deref(), Some("theirs"));
+}
+
+#[test]
+fn writes_keep_other_sessions_redo() {
+    let file = Scratch::new("history-redo.db");
+    let db = Database::init(&file.string()).unwrap();
+    db.upsert_concept("jwt", "theirs", 0.5).unwrap();
+    db.undo().unwrap();
+    give_away(&file);
+
+    db.upsert_concept("jws", "mine", 0.5).unwrap();
+    let conn = Connection::open(file.path()).unwrap();
+    let theirs: i64 = conn
+        .query_row("SELECT COUNT(*) FROM undo_log WHERE session = 'someone-else' AND undone = 1", [], |r| r.get(0))
+        .unwrap();
+    assert_eq!(theirs, 1);
+
+    // Our own redo is still cleared by a new write.
+    db.undo().unwrap();
+    db.upsert_concept("jose", "mine too", 0.5).unwrap();
+    assert_eq!(db.redo().unwrap(), None);
+}
+
+#[test]
+fn undo_log_gains_session_columns() {
+    let file = Scratch::new("history-migrate.db");
+    let conn = Connection::open(file.path()).unwrap();
+    conn.execute_batch(
+        "CREATE TABLE undo_log (
+           id INTEGER PRIMARY KEY AUTOINCREMENT, at TEXT NOT NULL, label TEXT NOT NULL,
+           before TEXT, after TEXT, undone INTEGER NOT NULL DEFAULT 0
+         );
+         INSERT INTO undo_log (at, label, before, after) VALUES ('then', 'old change', NULL, NULL);
+         PRAGMA user_version = 4;",
+    )
+    .unwrap();
+    drop(conn);
+
+    let db = Database::init(&file.string()).unwrap();
+    assert_eq!(db.schema_version().unwrap(), 5);
+    // The old entry belongs to no session.
+    assert_eq!(db.undo().unwrap(), None);
+    db.upsert_concept("jwt", "new", 0.5).unwrap();
+    assert_eq!(db.undo().unwrap().as_deref(), Some("learn jwt"));
+    assert_eq!(db.undo().unwrap(), None);
+}
+
+#[test]
+fn steps_refuse_to_lose_later_changes() {
+    let file = Scratch::new("history-changed.db");
+    let db = Database::init(&file.string()).unwrap();
+    db.upsert_concept("jwt", "mine", 0.5).unwrap();
+    db.add_episode("ok", "shipped").unwrap();
+    let conn = Connection::open(file.path()).unwrap();
+    let (id, at): (i64, String) =
+        conn.query_row("SELECT id, captured_at FROM episodes", [], |r| Ok((r.get(0)?, r.get(1)?))).unwrap();
+    conn.execute("UPDATE concepts SET definition = 'theirs' WHERE name = 'jwt'", []).unwrap();
+    conn.execute("UPDATE episodes SET summary = 'shipped, then rolled back'", []).unwrap();
+
+    let err = db.undo().unwrap_err().to_string();
+    assert_eq!(err, "cannot undo 'ep ok shipped': it was changed since; undoing would lose that change");
+    assert_eq!(definition(&db, "jwt").as_deref(), Some("theirs"));
+
+    // Once the row matches again the step goes through; the concept still refuses.
+    conn.execute("UPDATE episodes SET summary = 'shipped'", []).unwrap();
+    assert_eq!(db.undo().unwrap().as_deref(), Some("ep ok shipped"));
+    assert!(db.undo().unwrap_err().to_string().starts_with("cannot undo 'learn jwt'"));
+    assert_eq!(definition(&db, "jwt").as_deref(), Some("theirs"));
+
+    // Redo refuses to overwrite an episode that took the id meanwhile.
+    conn.execute("INSERT INTO episodes (id, captured_at, outcome, summary) VALUES (?1, ?2, 'note', 'other')", (id, &at))
+        .unwrap();
+    assert!(db.redo().unwrap_err().to_string().starts_with("cannot redo 'ep ok shipped'"));
+}
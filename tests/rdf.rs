@@ -0,0 +1,213 @@
+// Round trips through `rdf`: Knowledge -> text -> Knowledge in both formats,
+// documents written by other tools, and database -> file -> database through
+// DIALOG's `export rdf` and `import rdf`.
+mod common;
+
+use common::Scratch;
+use mother_terminal::bus::Bus;
+use mother_terminal::rdf::{self, ConceptRecord, Knowledge, Link, RdfFormat};
+use mother_terminal::ui::notify::Notifier;
+use mother_terminal::{Config, Database, Dialog};
+
+const BASE: &str = "https://kb.example/mother/";
+
+fn concept(name: &str, definition: &str, confidence: Option<f64>) -> ConceptRecord {
+    ConceptRecord { name: name.into(), definition: definition.into(), confidence }
+}
+
+fn link(from: &str, relation_type: &str, to: &str) -> Link {
+    Link { from: from.into(), relation_type: relation_type.into(), to: to.into() }
+}
+
+/// Names and definitions that need escaping in IRIs and strings, sorted
+/// the way `parse` returns them.
+fn sample() -> Knowledge {
+    Knowledge {
+        concepts: vec![
+            concept("-dash first", "starts with a dash", Some(0.4)),
+            concept("café", "non-ASCII: ☕ \t tab", Some(0.123456789)),
+            concept("jws", "JSON web signature", Some(0.75)),
+            concept("jwt", "a \"signed\" token;\nsee RFC 7519 \\ section 4", Some(0.9)),
+            concept("kb.v2 ~ draft", "dots, tildes and spaces", Some(1.0)),
+        ],
+        links: vec![
+            link("-dash first", "relates", "jwt"),
+            link("jose", "includes", "jwt"),
+            link("jwt", "part of", "jose"),
+            link("jwt", "uses", "jws"),
+        ],
+    }
+}
+
+#[test]
+fn turtle_round_trip() {
+    let text = rdf::render(&sample(), BASE, RdfFormat::Turtle);
+    assert_eq!(rdf::parse(&text, BASE, RdfFormat::Turtle).unwrap(), sample(), "{}", text);
+}
+
+#[test]
+fn jsonld_round_trip() {
+    let text = rdf::render(&sample(), BASE, RdfFormat::JsonLd);
+    assert_eq!(rdf::parse(&text, BASE, RdfFormat::JsonLd).unwrap(), sample(), "{}", text);
+}
+
+#[test]
+fn empty_round_trip() {
+    for format in [RdfFormat::Turtle, RdfFormat::JsonLd] {
+        let text = rdf::render(&Knowledge::default(), BASE, format);
+        assert_eq!(rdf::parse(&text, BASE, format).unwrap(), Knowledge::default());
+    }
+}
+
+#[test]
+fn iris_use_the_base() {
+    let text = rdf::render(&sample(), BASE, RdfFormat::Turtle);
+    assert!(text.contains("@prefix c: <https://kb.example/mother/concept/> ."));
+    assert!(text.contains("r:part%20of c:jose"));
+    assert!(text.contains("<https://kb.example/mother/concept/-dash%20first>"));
+
+    // Under another base neither the relations nor the confidences are ours;
+    // concepts are still read from their labels and comments.
+    let other = rdf::parse(&text, "urn:other:", RdfFormat::Turtle).unwrap();
+    let expected: Vec<_> = sample().concepts.into_iter().map(|c| concept(&c.name, &c.definition, None)).collect();
+    assert_eq!(other.concepts, expected);
+    assert!(other.links.is_empty());
+}
+
+#[test]
+fn reads_turtle_from_other_tools() {
+    let text = r#"
+        PREFIX rdfs: <http://www.w3.org/2000/01/rdf-schema#>
+        @prefix : <https://kb.example/mother/concept/> .
+        @prefix rel: <https://kb.example/mother/relation/> .
+        @prefix v: <https://kb.example/mother/vocab#> .
+        @base <https://kb.example/mother/concept/> .
+
+        # Labels are optional; the name then comes from the IRI.
+        :OAuth rdfs:comment """An authorization
+framework"""@en ;
+            v:confidence 0.8 ;
+            rel:uses <jwt>, :PKCE ;
+            rdfs:seeAlso [ rdfs:label "ignored" ] .
+
+        <jwt> rdfs:label 'JWT' ; rdfs:comment "token"^^<http://www.w3.org/2001/XMLSchema#string> ; .
+        :PKCE rdfs:comment "proof key" ; v:confidence "2.5" .
+    "#;
+    let k = rdf::parse(text, BASE, RdfFormat::Turtle).unwrap();
+    assert_eq!(
+        k.concepts,
+        vec![
+            concept("jwt", "token", None),
+            concept("oauth", "An authorization\nframework", Some(0.8)),
+            concept("pkce", "proof key", None),
+        ]
+    );
+    assert_eq!(k.links, vec![link("oauth", "uses", "jwt"), link("oauth", "uses", "pkce")]);
+}
+
+#[test]
+fn reads_jsonld_from_other_tools() {
+    let text = r#"[{
+        "@context": null
+    }]"#;
+    assert_eq!(rdf::parse(text, BASE, RdfFormat::JsonLd).unwrap(), Knowledge::default());
+
+    let text = r#"{
+        "@context": [
+            { "@vocab": "http://www.w3.org/2000/01/rdf-schema#", "@base": "https://kb.example/mother/concept/" },
+            { "uses": { "@id": "https://kb.example/mother/relation/uses", "@type": "@id" } }
+        ],
+        "@id": "oauth",
+        "comment": { "@value": "authorization framework", "@language": "en" },
+        "https://kb.example/mother/vocab#confidence": 0.6,
+        "uses": ["jwt", { "@id": "pkce", "comment": "proof key" }],
+        "unmapped": "dropped"
+    }"#;
+    let k = rdf::parse(text, BASE, RdfFormat::JsonLd).unwrap();
+    assert_eq!(
+        k.concepts,
+        vec![concept("oauth", "authorization framework", Some(0.6)), concept("pkce", "proof key", None)]
+    );
+    assert_eq!(k.links, vec![link("oauth", "uses", "jwt"), link("oauth", "uses", "pkce")]);
+}
+
+#[test]
+fn reports_syntax_errors_with_lines() {
+    let err = rdf::parse("@prefix c: <urn:c/> .\nc:a c:b \"open", BASE, RdfFormat::Turtle).unwrap_err();
+    assert_eq!(err, "line 2: unterminated string");
+    let err = rdf::parse("@prefix c: <urn:c/> .\n\nx:a c:b c:d .", BASE, RdfFormat::Turtle).unwrap_err();
+    assert_eq!(err, "line 3: undefined prefix 'x:'");
+    assert!(rdf::parse("{", BASE, RdfFormat::JsonLd).unwrap_err().starts_with("bad JSON"));
+}
+
+// (name, definition, confidence) and (from, type, to), sorted.
+type Contents = (Vec<(String, String, f64)>, Vec<(String, String, String)>);
+
+fn open_dialog(file: &Scratch) -> Dialog {
+    let config = Config { db_path: file.string(), rdf_base: BASE.into(), ..Config::default() };
+    let db = Database::init(&config.db_path).unwrap();
+    Dialog::new(db, Notifier::default(), Bus::default(), &config)
+}
+
+fn contents(file: &Scratch) -> Contents {
+    let db = Database::init(&file.string()).unwrap();
+    let mut concepts: Vec<_> =
+        db.list_concepts(1000).unwrap().into_iter().map(|c| (c.name, c.definition, c.confidence)).collect();
+    concepts.sort_by(|a, b| a.0.cmp(&b.0));
+    let mut relations: Vec<_> =
+        db.list_all_relations().unwrap().into_iter().map(|r| (r.from, r.relation_type, r.to)).collect();
+    relations.sort();
+    (concepts, relations)
+}
+
+#[test]
+fn database_round_trip() {
+    for ext in ["ttl", "jsonld"] {
+        let source = Scratch::new(&format!("rdf-src-{}.db", ext));
+        let target = Scratch::new(&format!("rdf-dst-{}.db", ext));
+        let file = Scratch::new(&format!("rdf-export.{}", ext));
+
+        let mut dialog = open_dialog(&source);
+        for line in [
+            "learn --confidence=0.9 jwt is a signed token",
+            "learn --confidence=0.7 jws is JSON web signature",
+            "learn \"part b\" is a name with a space",
+            "rel jwt uses jws",
+            "rel jwt \"part of\" jose",
+            "rel \"part b\" relates jwt",
+        ] {
+            dialog.run_line(line).unwrap();
+            if dialog.pending().is_some() {
+                dialog.decide(true);
+            }
+        }
+        dialog.run_line(&format!("export rdf \"{}\"", file.string())).unwrap();
+
+        let mut dialog = open_dialog(&target);
+        dialog.run_line(&format!("import rdf \"{}\"", file.string())).unwrap();
+        assert!(dialog.pending().is_none());
+        assert_eq!(contents(&target), contents(&source));
+
+        // Importing again changes nothing.
+        dialog.run_line(&format!("import rdf \"{}\"", file.string())).unwrap();
+        assert_eq!(contents(&target), contents(&source));
+    }
+}
+
+#[test]
+fn changed_definitions_are_proposed() {
+    let db = Scratch::new("rdf-proposals.db");
+    let knowledge = Knowledge { concepts: vec![concept("jwt", "a newer definition", Some(0.8))], links: Vec::new() };
+    let file = Scratch::file("rdf-proposals.ttl", &rdf::render(&knowledge, BASE, RdfFormat::Turtle));
+
+    let mut dialog = open_dialog(&db);
+    dialog.run_line("learn jwt is a signed token").unwrap();
+    dialog.decide(true);
+    let output = dialog.run_line(&format!("import rdf \"{}\"", file.string())).unwrap();
+    assert_eq!(dialog.pending(), Some("jwt"));
+    assert!(output.iter().any(|l| l.contains("a newer definition")), "{:?}", output);
+    assert_eq!(contents(&db).0, vec![("jwt".into(), "a signed token".into(), 0.4)]);
+
+    dialog.decide(true);
+    assert_eq!(contents(&db).0, vec![("jwt".into(), "a newer definition".into(), 0.8)]);
+}